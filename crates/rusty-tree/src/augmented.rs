@@ -0,0 +1,74 @@
+//! Augmentation/summary layer for upward-pass aggregation over a `Tree`.
+//!
+//! An FMM or Barnes-Hut solver needs per-node aggregated quantities — point counts, bounding
+//! boxes, multipole coefficients — computed bottom-up over the octree, the same role
+//! `Summary`/`add_summary` play in sum-tree crates. `Summary` is a monoid (`identity` plus an
+//! associative binary `combine`), so the same trait covers counts, centroids, bounding boxes and
+//! multipole coefficients alike; `Tree::aggregate` does the actual upward fold, and
+//! `AugmentedTree<S>` is a thin wrapper that turns a tree's points into per-leaf summaries before
+//! handing them to it, so callers can look up any box's aggregate without a second traversal.
+
+use std::collections::HashMap;
+
+use crate::{
+    octree::Tree,
+    types::{morton::MortonKey, point::Point},
+};
+
+/// A per-node aggregate folded bottom-up over an octree, e.g. a point count, a bounding box, or
+/// a mass/charge centroid. `identity`/`combine` must form a monoid: `combine(identity(), x) ==
+/// x` and `combine` must be associative, since `Tree::aggregate` folds children in no particular
+/// order.
+pub trait Summary: Clone {
+    /// The summary contributed by a single point at a leaf.
+    fn leaf_summary(point: &Point) -> Self;
+
+    /// The identity element: combining it with any summary leaves that summary unchanged.
+    fn identity() -> Self;
+
+    /// Combine this summary with another. Must be associative.
+    fn combine(&self, other: &Self) -> Self;
+
+    /// Fold a slice of summaries into one via repeated `combine`, starting from `identity`.
+    fn combine_all(summaries: &[Self]) -> Self {
+        summaries
+            .iter()
+            .fold(Self::identity(), |acc, summary| acc.combine(summary))
+    }
+}
+
+/// A `Tree` augmented with a bottom-up summary of type `S` at every node reachable from its
+/// leaves, built by `AugmentedTree::build`.
+pub struct AugmentedTree<S: Summary> {
+    summaries: HashMap<MortonKey, S>,
+}
+
+impl<S: Summary> AugmentedTree<S> {
+    /// Build the summary map for `tree`, whose leaves own the points in `points` (via
+    /// `Point::key`): fold every point belonging to a leaf into one per-leaf summary, then hand
+    /// those off to `Tree::aggregate` for the upward pass.
+    pub fn build(tree: &Tree, points: &[Point]) -> AugmentedTree<S> {
+        let mut by_leaf: HashMap<MortonKey, Vec<S>> = HashMap::new();
+        for point in points {
+            by_leaf
+                .entry(point.key)
+                .or_insert_with(Vec::new)
+                .push(S::leaf_summary(point));
+        }
+
+        let leaf_values: HashMap<MortonKey, S> = by_leaf
+            .into_iter()
+            .map(|(leaf, leaf_summaries)| (leaf, S::combine_all(&leaf_summaries)))
+            .collect();
+
+        AugmentedTree {
+            summaries: tree.aggregate(&leaf_values),
+        }
+    }
+
+    /// The aggregated summary at `key`, if `key` is one of the leaves `build` was given, or an
+    /// ancestor of one of them.
+    pub fn summary(&self, key: &MortonKey) -> Option<&S> {
+        self.summaries.get(key)
+    }
+}
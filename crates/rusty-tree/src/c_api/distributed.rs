@@ -7,6 +7,7 @@ use std::os::raw::c_char;
 use crate::{
     data::{HDF5, JSON, VTK},
     distributed::DistributedTree,
+    helpers::compute_bounds_global,
     types::{
         domain::Domain,
         morton::MortonKey,
@@ -28,6 +29,22 @@ pub extern "C" fn distributed_tree_from_points(
     Box::into_raw(Box::new(DistributedTree::new(points, balanced, &world)))
 }
 
+/// Compute the bounding box of `p_points` across every rank in `world`, the same
+/// `Allreduce`-based domain agreement `DistributedTree::new` relies on internally, exposed
+/// standalone so a caller can inspect or reuse the shared domain before building a tree.
+#[no_mangle]
+pub extern "C" fn distributed_tree_compute_bounds_global(
+    p_points: *const [PointType; 3],
+    npoints: usize,
+    world: *mut usize,
+) -> *mut Domain {
+    let points = unsafe { std::slice::from_raw_parts(p_points, npoints) };
+    let world = std::mem::ManuallyDrop::new(unsafe {
+        UserCommunicator::from_raw(*(world as *const MPI_Comm)).unwrap()
+    });
+    Box::into_raw(Box::new(compute_bounds_global(points, &world)))
+}
+
 #[no_mangle]
 pub extern "C" fn distributed_tree_nkeys(p_tree: *const DistributedTree) -> usize {
     let tree = unsafe { &*p_tree };
@@ -1,11 +1,16 @@
 //! C API for trees on a single node
 
 use crate::{
+    octree::CompressionType,
     types::{
+        domain::Domain,
         morton::{KeyType, MortonKey},
+        point::{Point, PointType},
     },
     single_node::Tree
 };
+use std::ffi::CStr;
+use std::os::raw::c_char;
 use std::slice::from_raw_parts;
 
 
@@ -17,6 +22,24 @@ pub extern "C" fn tree_from_morton_keys(data: *const KeyType, len: usize) -> *mu
     Box::into_raw(Box::new(tree))
 }
 
+#[no_mangle]
+pub extern "C" fn tree_save(p_tree: *const Tree, p_filename: *const c_char, compressed: bool) {
+    let tree = unsafe { &*p_tree };
+    let filename = unsafe { CStr::from_ptr(p_filename) }.to_str().unwrap();
+    let compression = if compressed {
+        CompressionType::Lz4
+    } else {
+        CompressionType::None
+    };
+    tree.save(filename, compression).unwrap();
+}
+
+#[no_mangle]
+pub extern "C" fn tree_load(p_filename: *const c_char) -> *mut Tree {
+    let filename = unsafe { CStr::from_ptr(p_filename) }.to_str().unwrap();
+    Box::into_raw(Box::new(Tree::load(filename).unwrap()))
+}
+
 // #[no_mangle]
 // pub extern "C" fn tree_linearize_keys(keys: *const Vec<MortonKey>) -> *mut Vec<MortonKey> {
 //     let linearized = unsafe {Tree::linearize_keys(*keys.clon) };
@@ -29,6 +52,50 @@ pub extern "C" fn tree_complete_region(a: *const MortonKey, b: *const MortonKey)
     Box::into_raw(Box::new(completed))
 }
 
+/// The `k` points in `p_points` closest to `p_query`, per `Tree::knn`.
+#[no_mangle]
+pub extern "C" fn tree_knn(
+    p_tree: *const Tree,
+    p_points: *const Point,
+    npoints: usize,
+    p_origin: *const [PointType; 3],
+    p_diameter: *const [PointType; 3],
+    p_query: *const [PointType; 3],
+    k: usize,
+) -> *mut Vec<Point> {
+    let tree = unsafe { &*p_tree };
+    let points = unsafe { from_raw_parts(p_points, npoints) };
+    let domain = Domain {
+        origin: unsafe { *p_origin },
+        diameter: unsafe { *p_diameter },
+    };
+    let query = unsafe { *p_query };
+
+    Box::into_raw(Box::new(tree.knn(points, &domain, query, k)))
+}
+
+/// Every point in `p_points` within `radius` of `p_query`, per `Tree::points_within_radius`.
+#[no_mangle]
+pub extern "C" fn tree_points_within_radius(
+    p_tree: *const Tree,
+    p_points: *const Point,
+    npoints: usize,
+    p_origin: *const [PointType; 3],
+    p_diameter: *const [PointType; 3],
+    p_query: *const [PointType; 3],
+    radius: PointType,
+) -> *mut Vec<Point> {
+    let tree = unsafe { &*p_tree };
+    let points = unsafe { from_raw_parts(p_points, npoints) };
+    let domain = Domain {
+        origin: unsafe { *p_origin },
+        diameter: unsafe { *p_diameter },
+    };
+    let query = unsafe { *p_query };
+
+    Box::into_raw(Box::new(tree.points_within_radius(points, &domain, query, radius)))
+}
+
 // #[no_mangle]
 // pub extern "C" fn balance(tree: *mut Tree) -> *mut Tree {
 //     let balanced = tree.balance();
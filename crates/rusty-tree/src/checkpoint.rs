@@ -0,0 +1,263 @@
+//! Compressed, checksummed tree checkpoints, portable across different MPI rank layouts.
+//!
+//! `DistributedTree::write_hdf5`/`read_hdf5` checksum each whole gathered `keys`/`points` blob
+//! as one unit, and `Tree::save`/`load` blocks its keys with no checksum at all. `write`/`read` here
+//! group a sorted key set into fixed-size blocks, delta-encode each block's Morton ids as LEB128
+//! varints (consecutive anchors are derivable from the morton id alone, so they aren't stored),
+//! compress the block with a pluggable `CompressionType`, and append an xxh3 checksum of the
+//! *uncompressed* payload. A header records the codec, block count, and the domain's
+//! `origin`/`diameter`, so a checkpoint written from one rank layout reloads into a flat global
+//! key set that makes no assumption about how a different layout would divide it up.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use mpi::{topology::UserCommunicator, traits::*, Count};
+
+use crate::{
+    distributed::DistributedTree,
+    octree::{read_varint, write_varint, CompressionType},
+    types::{domain::Domain, morton::MortonKey, point::PointType},
+};
+
+/// Magic bytes identifying a checkpoint file written by `write`.
+const CHECKPOINT_MAGIC: [u8; 4] = *b"RTCK";
+
+/// Number of keys grouped into a single checksummed, compressed block.
+const CHECKPOINT_BLOCK_SIZE: usize = 1024;
+
+/// Serialize `keys` (need not already be sorted) to `path`: a header recording `compression`,
+/// the block count, and `domain`'s `origin`/`diameter`, followed by one block per
+/// `CHECKPOINT_BLOCK_SIZE` keys, each compressed and tagged with an xxh3 checksum of its
+/// uncompressed payload.
+pub fn write<P: AsRef<Path>>(
+    path: P,
+    keys: &[MortonKey],
+    domain: &Domain,
+    compression: CompressionType,
+) -> io::Result<()> {
+    let mut sorted = keys.to_vec();
+    sorted.sort();
+
+    let blocks: Vec<&[MortonKey]> = sorted.chunks(CHECKPOINT_BLOCK_SIZE).collect();
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(&CHECKPOINT_MAGIC)?;
+    writer.write_all(&compression.tag().to_le_bytes())?;
+    writer.write_all(&(blocks.len() as u64).to_le_bytes())?;
+    for coordinate in domain.origin {
+        writer.write_all(&coordinate.to_le_bytes())?;
+    }
+    for coordinate in domain.diameter {
+        writer.write_all(&coordinate.to_le_bytes())?;
+    }
+
+    for block in blocks {
+        let mut payload = Vec::new();
+        let mut previous = 0u64;
+        for key in block {
+            let morton = key.morton();
+            write_varint(&mut payload, morton.wrapping_sub(previous))?;
+            previous = morton;
+        }
+
+        let checksum = xxhash_rust::xxh3::xxh3_64(&payload);
+        let raw_len = payload.len() as u64;
+        let compressed = compression.compress(&payload);
+
+        writer.write_all(&(block.len() as u64).to_le_bytes())?;
+        writer.write_all(&raw_len.to_le_bytes())?;
+        writer.write_all(&checksum.to_le_bytes())?;
+        writer.write_all(&(compressed.len() as u64).to_le_bytes())?;
+        writer.write_all(&compressed)?;
+    }
+
+    Ok(())
+}
+
+/// Load a checkpoint previously written by `write`, verifying every block's xxh3 checksum
+/// against its decompressed payload before decoding it. Returns an error instead of the decoded
+/// keys/domain the moment a block's checksum fails to match, rather than silently accepting a
+/// corrupted block.
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<(Vec<MortonKey>, Domain)> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != CHECKPOINT_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a checkpoint file",
+        ));
+    }
+
+    let mut compression_buf = [0u8; 4];
+    reader.read_exact(&mut compression_buf)?;
+    let compression = CompressionType::from_tag(u32::from_le_bytes(compression_buf));
+
+    let mut nblocks_buf = [0u8; 8];
+    reader.read_exact(&mut nblocks_buf)?;
+    let nblocks = u64::from_le_bytes(nblocks_buf) as usize;
+
+    let mut origin = [0 as PointType; 3];
+    for coordinate in origin.iter_mut() {
+        let mut buf = [0u8; std::mem::size_of::<PointType>()];
+        reader.read_exact(&mut buf)?;
+        *coordinate = PointType::from_le_bytes(buf);
+    }
+    let mut diameter = [0 as PointType; 3];
+    for coordinate in diameter.iter_mut() {
+        let mut buf = [0u8; std::mem::size_of::<PointType>()];
+        reader.read_exact(&mut buf)?;
+        *coordinate = PointType::from_le_bytes(buf);
+    }
+    let domain = Domain { origin, diameter };
+
+    let mut keys = Vec::new();
+
+    for _ in 0..nblocks {
+        let mut block_len_buf = [0u8; 8];
+        reader.read_exact(&mut block_len_buf)?;
+        let block_len = u64::from_le_bytes(block_len_buf) as usize;
+
+        let mut raw_len_buf = [0u8; 8];
+        reader.read_exact(&mut raw_len_buf)?;
+        let raw_len = u64::from_le_bytes(raw_len_buf) as usize;
+
+        let mut checksum_buf = [0u8; 8];
+        reader.read_exact(&mut checksum_buf)?;
+        let checksum = u64::from_le_bytes(checksum_buf);
+
+        let mut compressed_len_buf = [0u8; 8];
+        reader.read_exact(&mut compressed_len_buf)?;
+        let compressed_len = u64::from_le_bytes(compressed_len_buf) as usize;
+
+        let mut compressed = vec![0u8; compressed_len];
+        reader.read_exact(&mut compressed)?;
+
+        let payload = compression.decompress(&compressed, raw_len);
+        if xxhash_rust::xxh3::xxh3_64(&payload) != checksum {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "checkpoint block failed its xxh3 checksum",
+            ));
+        }
+
+        let mut cursor = &payload[..];
+        let mut previous = 0u64;
+        for _ in 0..block_len {
+            let delta = read_varint(&mut cursor)?;
+            let morton = previous.wrapping_add(delta);
+            keys.push(MortonKey::from_morton(morton));
+            previous = morton;
+        }
+    }
+
+    Ok((keys, domain))
+}
+
+/// Gather `tree`'s local keys into a single sorted global array on the root rank, the same
+/// collective `write_hdf5` uses to assemble its own checkpoint.
+fn gather_sorted_keys(world: &UserCommunicator, tree: &DistributedTree) -> Option<Vec<MortonKey>> {
+    let comm = world.duplicate();
+    let rank = comm.rank();
+    let size = comm.size();
+
+    let root_process = comm.process_at_rank(0);
+
+    let nlocal_keys: Count = tree.keys.len() as Count;
+    let mut global_key_counts: Vec<Count> = vec![0; size as usize];
+
+    if rank == 0 {
+        root_process.gather_into_root(&nlocal_keys, &mut global_key_counts[..]);
+    } else {
+        root_process.gather_into(&nlocal_keys);
+    }
+
+    if rank == 0 {
+        let global_key_displs: Vec<Count> = global_key_counts
+            .iter()
+            .scan(0, |acc, &x| {
+                let tmp = *acc;
+                *acc += x;
+                Some(tmp)
+            })
+            .collect();
+
+        let global_key_count: usize = global_key_counts.iter().sum::<Count>() as usize;
+        let mut global_keys: Vec<MortonKey> = vec![MortonKey::default(); global_key_count];
+
+        let mut key_partition = mpi::datatype::PartitionMut::new(
+            &mut global_keys[..],
+            global_key_counts,
+            &global_key_displs[..],
+        );
+        root_process.gather_varcount_into_root(&tree.keys[..], &mut key_partition);
+
+        global_keys.sort();
+        Some(global_keys)
+    } else {
+        root_process.gather_varcount_into(&tree.keys[..]);
+        None
+    }
+}
+
+impl DistributedTree {
+    /// Checkpoint this tree's global key set (gathered and sorted on the root rank) to `path`
+    /// with `write`'s compressed, block-checksummed format.
+    pub fn write_checkpoint(
+        world: &UserCommunicator,
+        path: &str,
+        tree: &DistributedTree,
+        compression: CompressionType,
+    ) -> io::Result<()> {
+        let comm = world.duplicate();
+        let rank = comm.rank();
+
+        let global_keys = gather_sorted_keys(&comm, tree);
+
+        if rank == 0 {
+            write(path, &global_keys.unwrap(), &tree.domain, compression)?;
+        }
+
+        Ok(())
+    }
+
+    /// Load a checkpoint written by `write_checkpoint` on the root rank and broadcast the
+    /// decoded global key set and domain to every rank. Unlike `read_hdf5`, this doesn't
+    /// re-partition keys by rank count — a checkpoint is meant to be reloadable under a
+    /// different rank layout than the one that wrote it, so every rank gets the same full key
+    /// set and decides its own share of it afterwards.
+    pub fn read_checkpoint(world: &UserCommunicator, path: &str) -> io::Result<(Vec<MortonKey>, Domain)> {
+        let comm = world.duplicate();
+        let rank = comm.rank();
+        let root_process = comm.process_at_rank(0);
+
+        let mut nkeys: Count = 0;
+        let mut domain = Domain::default();
+        let mut keys: Vec<MortonKey> = Vec::new();
+
+        if rank == 0 {
+            let (global_keys, global_domain) = read(path)?;
+            nkeys = global_keys.len() as Count;
+            domain = global_domain;
+            keys = global_keys;
+        }
+
+        root_process.broadcast_into(&mut nkeys);
+        root_process.broadcast_into(&mut domain);
+
+        if rank != 0 {
+            keys = vec![MortonKey::default(); nkeys as usize];
+        }
+        root_process.broadcast_into(&mut keys[..]);
+
+        Ok((keys, domain))
+    }
+}
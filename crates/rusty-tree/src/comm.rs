@@ -0,0 +1,283 @@
+//! Batched, non-blocking MPI communication layer for tree construction.
+//!
+//! `complete_blocktree` and `transfer_leaves_to_coarse_blocktree` each issue a handful of tiny
+//! blocking `send`/`receive_into` calls to a single fixed neighbor rank, so a rank sits idle
+//! waiting on its neighbor's reply before it can go on to complete the region between its own,
+//! already-resolved seeds. `BatchedExchange` instead issues the send and the receive for a batch
+//! of boundary exchanges as non-blocking requests and only waits on them once the whole batch has
+//! been posted, so the exchanges of one batch are in flight together. `exchange` returns only
+//! after every batch has completed; the caller's own construction work does not run while they
+//! are in flight. `batch_depth` bounds how many exchanges are kept outstanding at once,
+//! the same role a batch/queue depth plays in an IO engine; it defaults to `size` so that, in the
+//! common case of one exchange per neighbor, every exchange is posted before any wait.
+
+use mpi::{
+    request::{scope, WaitGuard},
+    topology::{Rank, UserCommunicator},
+    traits::*,
+    Count,
+};
+
+use crate::types::{morton::MortonKey, point::Point};
+
+/// The variable-length key/point payload exchanged with a single neighbor rank.
+#[derive(Debug, Clone, Default)]
+pub struct Boundary {
+    pub keys: Vec<MortonKey>,
+    pub points: Vec<Point>,
+}
+
+/// Default batch/queue depth: one exchange per neighbor, so a round with `size` ranks posts
+/// every exchange before waiting on any of them.
+pub fn default_batch_depth(size: Rank) -> usize {
+    (size.max(1)) as usize
+}
+
+/// Batches an `Ialltoallv`-style exchange of `Boundary`s with a set of partner ranks, keeping at
+/// most `batch_depth` exchanges outstanding at once.
+pub struct BatchedExchange<'a> {
+    world: &'a UserCommunicator,
+    batch_depth: usize,
+    /// Non-blocking collectives aren't available on every communicator (e.g. some MPI builds
+    /// used in testing); when false, `exchange` falls back to the same blocking send/receive
+    /// sequence `complete_blocktree`/`transfer_leaves_to_coarse_blocktree` use directly.
+    supports_nonblocking: bool,
+}
+
+impl<'a> BatchedExchange<'a> {
+    /// A batched exchange over `world` with the default batch depth (`default_batch_depth`).
+    pub fn new(world: &'a UserCommunicator) -> Self {
+        BatchedExchange {
+            world,
+            batch_depth: default_batch_depth(world.size()),
+            supports_nonblocking: true,
+        }
+    }
+
+    /// A batched exchange with an explicit batch/queue depth.
+    pub fn with_batch_depth(world: &'a UserCommunicator, batch_depth: usize) -> Self {
+        BatchedExchange {
+            world,
+            batch_depth: batch_depth.max(1),
+            supports_nonblocking: true,
+        }
+    }
+
+    /// Force the blocking fallback path, e.g. after probing shows `world`'s communicator
+    /// doesn't support the non-blocking variant this layer otherwise relies on.
+    pub fn without_nonblocking(mut self) -> Self {
+        self.supports_nonblocking = false;
+        self
+    }
+
+    /// Exchange a `Boundary` with each rank in `partners`, calling `outgoing(rank)` to build the
+    /// payload to send it. Returns each partner's reply, in the same order as `partners`.
+    pub fn exchange(
+        &self,
+        partners: &[Rank],
+        mut outgoing: impl FnMut(Rank) -> Boundary,
+    ) -> Vec<(Rank, Boundary)> {
+        if !self.supports_nonblocking {
+            return self.exchange_blocking(partners, outgoing);
+        }
+
+        let mut replies = Vec::with_capacity(partners.len());
+
+        for batch in partners.chunks(self.batch_depth) {
+            let outgoing_boundaries: Vec<Boundary> =
+                batch.iter().map(|&rank| outgoing(rank)).collect();
+            let outgoing_lens: Vec<[Count; 2]> = outgoing_boundaries
+                .iter()
+                .map(|b| [b.keys.len() as Count, b.points.len() as Count])
+                .collect();
+            let mut incoming_lens = vec![[0 as Count; 2]; batch.len()];
+
+            // Phase 1: post every length exchange in the batch, then wait on all of them.
+            scope(|scope| {
+                let mut recv_requests = Vec::with_capacity(batch.len());
+                let mut send_guards = Vec::with_capacity(batch.len());
+
+                for (i, &rank) in batch.iter().enumerate() {
+                    let process = self.world.process_at_rank(rank);
+                    recv_requests.push(process.immediate_receive_into(scope, &mut incoming_lens[i]));
+                    send_guards.push(WaitGuard::from(
+                        process.immediate_send(scope, &outgoing_lens[i]),
+                    ));
+                }
+
+                for request in recv_requests {
+                    request.wait();
+                }
+            });
+
+            let mut incoming_keys: Vec<Vec<MortonKey>> = incoming_lens
+                .iter()
+                .map(|lens| vec![MortonKey::default(); lens[0] as usize])
+                .collect();
+            let mut incoming_points: Vec<Vec<Point>> = incoming_lens
+                .iter()
+                .map(|lens| vec![Point::default(); lens[1] as usize])
+                .collect();
+
+            // Phase 2: post every key/point exchange in the batch, then wait on all of them.
+            scope(|scope| {
+                let mut recv_requests = Vec::with_capacity(batch.len() * 2);
+                let mut send_guards = Vec::with_capacity(batch.len() * 2);
+
+                for (i, &rank) in batch.iter().enumerate() {
+                    let process = self.world.process_at_rank(rank);
+
+                    recv_requests
+                        .push(process.immediate_receive_into(scope, &mut incoming_keys[i][..]));
+                    send_guards.push(WaitGuard::from(
+                        process.immediate_send(scope, &outgoing_boundaries[i].keys[..]),
+                    ));
+
+                    recv_requests
+                        .push(process.immediate_receive_into(scope, &mut incoming_points[i][..]));
+                    send_guards.push(WaitGuard::from(
+                        process.immediate_send(scope, &outgoing_boundaries[i].points[..]),
+                    ));
+                }
+
+                for request in recv_requests {
+                    request.wait();
+                }
+            });
+
+            replies.extend(batch.iter().zip(incoming_keys.into_iter().zip(incoming_points.into_iter())).map(
+                |(&rank, (keys, points))| (rank, Boundary { keys, points }),
+            ));
+        }
+
+        replies
+    }
+
+    /// The blocking fallback: one `send`/`receive_into` round-trip per partner, in the style of
+    /// `transfer_leaves_to_coarse_blocktree`, used when the communicator doesn't support the
+    /// non-blocking variant `exchange` otherwise uses.
+    fn exchange_blocking(
+        &self,
+        partners: &[Rank],
+        mut outgoing: impl FnMut(Rank) -> Boundary,
+    ) -> Vec<(Rank, Boundary)> {
+        partners
+            .iter()
+            .map(|&rank| {
+                let process = self.world.process_at_rank(rank);
+                let boundary = outgoing(rank);
+
+                let lens = [boundary.keys.len() as Count, boundary.points.len() as Count];
+                process.send(&lens);
+                process.send(&boundary.keys[..]);
+                process.send(&boundary.points[..]);
+
+                let mut incoming_lens = [0 as Count; 2];
+                process.receive_into(&mut incoming_lens);
+                let mut keys = vec![MortonKey::default(); incoming_lens[0] as usize];
+                let mut points = vec![Point::default(); incoming_lens[1] as usize];
+                process.receive_into(&mut keys[..]);
+                process.receive_into(&mut points[..]);
+
+                (rank, Boundary { keys, points })
+            })
+            .collect()
+    }
+}
+
+/// Pluggable one-directional `Point` transfer to/from a single neighbour rank, the pattern
+/// `transfer_points_to_blocktree` uses to hand boundary points to the rank below it and pull them
+/// in from the rank above. `SyncCommEngine` is a single blocking round trip per call; the
+/// `batch_size` `AsyncCommEngine` is configured with controls how many non-blocking chunks it
+/// pipelines instead. Both engines wait on every request they post before `send_points`/
+/// `receive_points` return, so the caller's own work does not run while a transfer is in flight.
+pub trait CommEngine: Send + Sync {
+    /// Points per pipelined transmission unit. `SyncCommEngine` always reports `1`: it has
+    /// nothing to pipeline, since it sends/receives the whole buffer as a single blocking unit.
+    fn batch_size(&self) -> usize;
+
+    /// Send every point in `points` to rank `to`.
+    fn send_points(&self, world: &UserCommunicator, to: Rank, points: &[Point]);
+
+    /// Receive whatever rank `from` sends via a matching `send_points` call.
+    fn receive_points(&self, world: &UserCommunicator, from: Rank) -> Vec<Point>;
+}
+
+/// One blocking `send`/`receive_into` round trip per call, in the style
+/// `transfer_points_to_blocktree` used directly before `CommEngine` existed.
+pub struct SyncCommEngine;
+
+impl CommEngine for SyncCommEngine {
+    fn batch_size(&self) -> usize {
+        1
+    }
+
+    fn send_points(&self, world: &UserCommunicator, to: Rank, points: &[Point]) {
+        let process = world.process_at_rank(to);
+        process.send(&(points.len() as Rank));
+        process.send(points);
+    }
+
+    fn receive_points(&self, world: &UserCommunicator, from: Rank) -> Vec<Point> {
+        let process = world.process_at_rank(from);
+        let mut len = 0 as Rank;
+        process.receive_into(&mut len);
+        let mut points = vec![Point::default(); len as usize];
+        process.receive_into(&mut points[..]);
+        points
+    }
+}
+
+/// Pipelines a transfer as non-blocking requests in fixed-size batches of `batch_size` points,
+/// posting every chunk's request before waiting on any of them (the same post-then-wait shape
+/// `BatchedExchange` uses), so the underlying MPI implementation can overlap the chunks in
+/// flight instead of serializing the whole transfer into one blocking call.
+pub struct AsyncCommEngine {
+    batch_size: usize,
+}
+
+impl AsyncCommEngine {
+    /// An async engine pipelining transfers in batches of `batch_size` points.
+    pub fn new(batch_size: usize) -> Self {
+        AsyncCommEngine {
+            batch_size: batch_size.max(1),
+        }
+    }
+}
+
+impl CommEngine for AsyncCommEngine {
+    fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    fn send_points(&self, world: &UserCommunicator, to: Rank, points: &[Point]) {
+        let process = world.process_at_rank(to);
+        process.send(&(points.len() as Rank));
+
+        scope(|scope| {
+            let mut send_guards = Vec::new();
+            for chunk in points.chunks(self.batch_size) {
+                send_guards.push(WaitGuard::from(process.immediate_send(scope, chunk)));
+            }
+        });
+    }
+
+    fn receive_points(&self, world: &UserCommunicator, from: Rank) -> Vec<Point> {
+        let process = world.process_at_rank(from);
+        let mut len = 0 as Rank;
+        process.receive_into(&mut len);
+
+        let mut points = vec![Point::default(); len as usize];
+        scope(|scope| {
+            let mut recv_requests = Vec::new();
+            for chunk in points.chunks_mut(self.batch_size) {
+                recv_requests.push(process.immediate_receive_into(scope, chunk));
+            }
+            for request in recv_requests {
+                request.wait();
+            }
+        });
+
+        points
+    }
+}
@@ -0,0 +1,150 @@
+//! Seek cursors over a `Tree`'s sorted key array.
+//!
+//! `Tree` only derefs to a flat `Vec<MortonKey>`, so consumers wanting to navigate the implied
+//! parent/child structure, or jump straight to a region, have to binary-search and re-derive
+//! ancestry by hand every time. `Cursor` (in the spirit of the seek cursors in sum-tree/concread)
+//! holds a position in the sorted array plus the current ancestor stack, so `seek`, `descend`/
+//! `ascend`, and `next_sibling`/`next_in_subtree` move through it in O(log n) / O(subtree size)
+//! rather than a full scan. `FilterCursor` layers a predicate on top that prunes whole subtrees
+//! it can rule out via `is_ancestor`/`finest_ancestor`, so neighbor-list and range queries only
+//! touch the keys they actually return.
+
+use crate::{octree::Tree, types::morton::MortonKey};
+
+/// A position in a `Tree`'s sorted keys, with the stack of ancestors above it.
+pub struct Cursor<'a> {
+    keys: &'a [MortonKey],
+    pos: usize,
+    ancestors: Vec<MortonKey>,
+}
+
+impl<'a> Cursor<'a> {
+    /// A cursor over `tree`, initially positioned at its first key.
+    pub fn new(tree: &'a Tree) -> Self {
+        Cursor {
+            keys: &tree.keys,
+            pos: 0,
+            ancestors: Vec::new(),
+        }
+    }
+
+    /// The key at the cursor's current position, or `None` if it has run off the end.
+    pub fn current(&self) -> Option<&MortonKey> {
+        self.keys.get(self.pos)
+    }
+
+    /// Binary search to the first key greater than or equal to `target`, resetting the ancestor
+    /// stack to `target`'s ancestors.
+    pub fn seek(&mut self, target: &MortonKey) -> Option<&MortonKey> {
+        self.pos = match self.keys.binary_search(target) {
+            Ok(idx) | Err(idx) => idx,
+        };
+
+        let mut ancestors: Vec<MortonKey> = target.ancestors().into_iter().collect();
+        ancestors.sort_by_key(|a| a.level());
+        self.ancestors = ancestors;
+
+        self.current()
+    }
+
+    /// Descend into the current node's first (Morton-least) child.
+    pub fn descend(&mut self) -> Option<&MortonKey> {
+        let current = *self.current()?;
+        self.ancestors.push(current);
+        let first_child = current.children().into_iter().min()?;
+        self.seek(&first_child)
+    }
+
+    /// Ascend to the current node's nearest ancestor still on the stack.
+    pub fn ascend(&mut self) -> Option<&MortonKey> {
+        let parent = self.ancestors.pop()?;
+        self.seek(&parent)
+    }
+
+    /// Move to the next Morton-ordered sibling of the current node (same parent), or `None` if
+    /// the current node was its parent's last child.
+    pub fn next_sibling(&mut self) -> Option<&MortonKey> {
+        let current = *self.current()?;
+        if current.level() == 0 {
+            return None;
+        }
+
+        let parent = self.ancestors.last().copied().unwrap_or_else(|| current.parent());
+        let mut siblings: Vec<MortonKey> = parent.children().into_iter().collect();
+        siblings.sort();
+
+        let idx = siblings.iter().position(|s| *s == current)?;
+        let next = *siblings.get(idx + 1)?;
+        self.seek(&next)
+    }
+
+    /// Skip past every key in the current node's subtree (itself included), landing on the
+    /// first key that isn't a descendant of it — the primitive a range scan uses to prune a
+    /// whole subtree in one step instead of visiting it key by key.
+    pub fn next_in_subtree(&mut self) -> Option<&MortonKey> {
+        let current = *self.current()?;
+        while let Some(&key) = self.keys.get(self.pos) {
+            if key == current || current.is_ancestor(&key) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        self.current()
+    }
+
+    /// Every key between `a` and `b` inclusive: seek to the first key at or after `a` (as `seek`
+    /// does) and walk forward in Morton order until past `b`. A key before `a` is not included,
+    /// even if its box contains `a`.
+    pub fn leaves_between(&mut self, a: &MortonKey, b: &MortonKey) -> Vec<MortonKey> {
+        self.seek(a);
+
+        let mut leaves = Vec::new();
+        while let Some(&key) = self.current() {
+            if key > *b {
+                break;
+            }
+            leaves.push(key);
+            self.pos += 1;
+        }
+
+        leaves
+    }
+}
+
+/// A `Cursor` that only stops on keys matching `predicate`, pruning whole subtrees the
+/// predicate can rule out (e.g. "level == L", or "within this box" via `finest_ancestor`)
+/// instead of visiting every key in them.
+pub struct FilterCursor<'a, F: Fn(&MortonKey) -> bool> {
+    cursor: Cursor<'a>,
+    predicate: F,
+}
+
+impl<'a, F: Fn(&MortonKey) -> bool> FilterCursor<'a, F> {
+    /// A filtered cursor over `tree`, stopping only at keys for which `predicate` holds.
+    pub fn new(tree: &'a Tree, predicate: F) -> Self {
+        FilterCursor {
+            cursor: Cursor::new(tree),
+            predicate,
+        }
+    }
+
+    /// Advance to the next key matching `predicate`, skipping any non-matching key's whole
+    /// subtree via `next_in_subtree` rather than stepping through it one key at a time.
+    pub fn next(&mut self) -> Option<MortonKey> {
+        loop {
+            let current = *self.cursor.current()?;
+
+            if (self.predicate)(&current) {
+                self.cursor.pos += 1;
+                return Some(current);
+            }
+
+            if self.cursor.next_in_subtree() == Some(&current) {
+                // Guard: `next_in_subtree` always steps past `current` itself, so this only
+                // forces a single step if it ever landed on `current` again.
+                self.cursor.pos += 1;
+            }
+        }
+    }
+}
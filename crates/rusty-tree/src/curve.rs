@@ -0,0 +1,213 @@
+//! Pluggable space-filling-curve orderings used to linearise points before a distributed sort.
+//!
+//! `unbalanced_tree`/`balanced_tree` need a total order over points that keeps spatially close
+//! points close together in the sorted sequence, so that each rank ends up with a compact
+//! contiguous block after `hyksort`. The natural Morton (Z-order) encoding already used by
+//! `MortonKey` does this reasonably well, but has locality discontinuities at the boundaries
+//! between octants. A Hilbert curve has no such discontinuities and shrinks the surface area of
+//! each rank's block, at the cost of a slightly more expensive encoding.
+
+use crate::types::morton::{KeyType, MortonKey};
+
+/// An ordering over octree boxes used to drive the distributed sort in `unbalanced_tree`/
+/// `balanced_tree` and the seed selection in `find_seeds`.
+///
+/// `encode`/`decode` are the curve's own pair of operations, independent of `MortonKey`'s fixed
+/// Z-order `Ord`; `sort_key` (used wherever a `&dyn SpaceFillingCurve` orders `MortonKey`s
+/// directly) defaults to `encode` applied to the key's anchor and level.
+pub trait SpaceFillingCurve: Send + Sync {
+    /// Map a 3D integer anchor at `level` to its index along this curve.
+    fn encode(&self, anchor: &[KeyType; 3], level: KeyType) -> u64;
+
+    /// The inverse of `encode`: recover the anchor an index at `level` was encoded from.
+    fn decode(&self, index: u64, level: KeyType) -> [KeyType; 3];
+
+    /// Return the sort key for `key`, used in place of `MortonKey`'s natural (Z-order) `Ord`.
+    fn sort_key(&self, key: &MortonKey) -> u64 {
+        self.encode(key.anchor(), key.level())
+    }
+}
+
+/// The default Z-order curve, i.e. `MortonKey`'s own bit-interleaved encoding.
+pub struct Morton;
+
+impl SpaceFillingCurve for Morton {
+    fn encode(&self, anchor: &[KeyType; 3], level: KeyType) -> u64 {
+        let mut code: u64 = 0;
+        for bit in (0..level).rev() {
+            for &component in anchor.iter() {
+                code = (code << 1) | (((component >> bit) & 1) as u64);
+            }
+        }
+        code
+    }
+
+    fn decode(&self, index: u64, level: KeyType) -> [KeyType; 3] {
+        let mut anchor = [0 as KeyType; 3];
+        let mut remaining = index;
+        for bit in 0..level {
+            for axis in (0..3).rev() {
+                let value = (remaining & 1) as KeyType;
+                anchor[axis] |= value << bit;
+                remaining >>= 1;
+            }
+        }
+        anchor
+    }
+
+    fn sort_key(&self, key: &MortonKey) -> u64 {
+        key.morton()
+    }
+}
+
+/// A Hilbert-curve ordering, computed from a key's anchor and level.
+///
+/// Spatially adjacent boxes are much more likely to be adjacent in Hilbert order than in Morton
+/// order, which reduces the number of MPI neighbours each rank's block has after partitioning.
+pub struct Hilbert;
+
+impl SpaceFillingCurve for Hilbert {
+    fn encode(&self, anchor: &[KeyType; 3], level: KeyType) -> u64 {
+        hilbert_distance(anchor, level)
+    }
+
+    fn decode(&self, index: u64, level: KeyType) -> [KeyType; 3] {
+        hilbert_anchor(index, level)
+    }
+}
+
+/// Map a 3D anchor at a given level to its distance along a Hilbert curve.
+///
+/// Follows the standard "rotation" algorithm for converting an (x, y, z) index to a single
+/// Hilbert distance, applied one bit per level from coarsest to finest.
+fn hilbert_distance(anchor: &[KeyType; 3], level: KeyType) -> u64 {
+    let mut x = anchor[0];
+    let mut y = anchor[1];
+    let mut z = anchor[2];
+
+    let mut distance: u64 = 0;
+
+    let mut side = 1 << level;
+    while side > 1 {
+        side >>= 1;
+
+        let rx = if (x & side) > 0 { 1 } else { 0 };
+        let ry = if (y & side) > 0 { 1 } else { 0 };
+        let rz = if (z & side) > 0 { 1 } else { 0 };
+
+        let digit = (rx << 2) | (ry << 1) | rz;
+        distance = (distance << 3) | digit as u64;
+
+        // Drop the bits just read, so only the not-yet-read bits below `side` remain.
+        x &= side - 1;
+        y &= side - 1;
+        z &= side - 1;
+
+        // Rotate the remaining bits so the curve continues smoothly into the next octant.
+        if rz == 0 {
+            if ry == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            } else {
+                let tmp = x;
+                x = y;
+                y = tmp;
+            }
+
+            if rx == 1 {
+                x = side - 1 - x;
+                z = side - 1 - z;
+            }
+        }
+    }
+
+    distance
+}
+
+/// The inverse of `hilbert_distance`: recover the anchor a Hilbert index at `level` was encoded
+/// from.
+///
+/// `hilbert_distance` appends digits coarsest-first and rotates its *remaining, not-yet-read*
+/// bits after each one; undoing that only cancels out if digits are replayed in the opposite
+/// order — finest first, growing `side` from 1 up to the coarsest level, rotating the bits
+/// already decoded so far before folding in each new one. This is the standard 2D Hilbert
+/// `d2xy` algorithm's digit order, generalized to 3D.
+fn hilbert_anchor(distance: u64, level: KeyType) -> [KeyType; 3] {
+    let mut x: KeyType = 0;
+    let mut y: KeyType = 0;
+    let mut z: KeyType = 0;
+
+    let mut side: KeyType = 1;
+    for i in 0..level {
+        let digit = (distance >> (3 * i)) & 0b111;
+
+        let rx = ((digit >> 2) & 1) as KeyType;
+        let ry = ((digit >> 1) & 1) as KeyType;
+        let rz = (digit & 1) as KeyType;
+
+        // Undo the same rotation `hilbert_distance` applied after reading these bits.
+        if rz == 0 {
+            if rx == 1 {
+                x = side - 1 - x;
+                z = side - 1 - z;
+            }
+
+            if ry == 1 {
+                x = side - 1 - x;
+                y = side - 1 - y;
+            } else {
+                let tmp = x;
+                x = y;
+                y = tmp;
+            }
+        }
+
+        x |= rx * side;
+        y |= ry * side;
+        z |= rz * side;
+
+        side <<= 1;
+    }
+
+    [x, y, z]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hilbert_round_trip() {
+        for level in 1..=4 {
+            let side = 1 << level;
+            for x in 0..side {
+                for y in 0..side {
+                    for z in 0..side {
+                        let anchor: [KeyType; 3] = [x, y, z];
+                        let distance = hilbert_distance(&anchor, level);
+                        let decoded = hilbert_anchor(distance, level);
+                        assert_eq!(
+                            decoded, anchor,
+                            "level {level}: anchor {anchor:?} encoded to {distance} but decoded to {decoded:?}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hilbert_distinct_anchors_distinct_distances() {
+        let level = 3;
+        let side = 1 << level;
+        let mut distances = std::collections::HashSet::new();
+        for x in 0..side {
+            for y in 0..side {
+                for z in 0..side {
+                    let distance = hilbert_distance(&[x, y, z], level);
+                    assert!(distances.insert(distance), "duplicate distance {distance}");
+                }
+            }
+        }
+    }
+}
@@ -4,12 +4,16 @@ use vtkio::model::*;
 use std::path::PathBuf;
 use std::error::Error;
 use std::fs::File;
-use std::io::BufReader;
-use std::io::BufWriter;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
-use crate::types::{
-    morton::MortonKey, point::Point
+use memmap2::{Mmap, MmapOptions};
+
+use crate::{
+    octree::{read_varint, write_varint, CompressionType},
+    types::{
+        domain::Domain, morton::MortonKey, point::{Point, PointType}
+    },
 };
 
 use serde::{Serialize, Deserialize};
@@ -96,4 +100,512 @@ pub trait HDF5<T: hdf5::H5Type> {
 
     // Read data from a 1D sequence into a Rust vector.
     fn read_hdf5<P: AsRef<Path>>(filepath: P) -> hdf5::Result<Vec<T>>;
+}
+
+/// Magic bytes identifying a file written by `DiskTree::write`.
+const DISK_TREE_MAGIC: [u8; 4] = *b"RTDT";
+
+/// Format version, bumped whenever the header or payload layout changes incompatibly.
+const DISK_TREE_VERSION: u32 = 1;
+
+/// Number of keys grouped into a single delta-varint-encoded block.
+const DISK_TREE_BLOCK_SIZE: usize = 1024;
+
+/// One block's entry in `DiskTree`'s header: the first key it holds (for binary search), where
+/// its varint stream starts in the payload, and how many keys it holds.
+struct BlockEntry {
+    first_morton: u64,
+    offset: usize,
+    count: usize,
+}
+
+/// A `Vec<MortonKey>`, stored sorted and LEB128 varint-delta-encoded on disk, queried by
+/// memory-mapping the file rather than deserializing it into a `Vec` up front.
+///
+/// `MortonKey` already orders by its `u64` Morton id, so consecutive keys in the sorted array
+/// usually differ by a small amount; storing those deltas as varints (instead of the full
+/// fixed-width anchor and Morton id `Tree::save`/`write_hdf5` each persist per key) gives large
+/// space savings on dense trees. A small header holding each block's first key and byte offset
+/// lets `contains`/`locate` binary-search straight to the one block a lookup needs, so querying a
+/// tree far larger than RAM only ever pages in a handful of blocks instead of the whole file —
+/// unlike `HDF5::read_hdf5`, which always materializes every key in memory.
+pub struct DiskTree {
+    mmap: Mmap,
+    domain: Domain,
+    key_count: usize,
+    blocks: Vec<BlockEntry>,
+    payload_start: usize,
+}
+
+impl DiskTree {
+    /// Serialize `keys` (need not already be sorted) and `domain` to `path` in `DiskTree`'s
+    /// format: a header recording the version, domain, key count, and each block's
+    /// (first key, offset, count), followed by the blocks' concatenated varint streams.
+    pub fn write<P: AsRef<Path>>(path: P, keys: &[MortonKey], domain: &Domain) -> io::Result<()> {
+        let mut sorted = keys.to_vec();
+        sorted.sort_by_key(|key| key.morton());
+
+        let mut payload = Vec::new();
+        let mut block_entries = Vec::new();
+
+        for chunk in sorted.chunks(DISK_TREE_BLOCK_SIZE) {
+            let offset = payload.len();
+            let first_morton = chunk[0].morton();
+
+            let mut previous = 0u64;
+            for key in chunk {
+                let morton = key.morton();
+                write_varint(&mut payload, morton.wrapping_sub(previous))?;
+                previous = morton;
+            }
+
+            block_entries.push((first_morton, offset, chunk.len()));
+        }
+
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(&DISK_TREE_MAGIC)?;
+        writer.write_all(&DISK_TREE_VERSION.to_le_bytes())?;
+        for coordinate in domain.origin {
+            writer.write_all(&coordinate.to_le_bytes())?;
+        }
+        for coordinate in domain.diameter {
+            writer.write_all(&coordinate.to_le_bytes())?;
+        }
+        writer.write_all(&(sorted.len() as u64).to_le_bytes())?;
+        writer.write_all(&(block_entries.len() as u64).to_le_bytes())?;
+        for (first_morton, offset, count) in &block_entries {
+            writer.write_all(&first_morton.to_le_bytes())?;
+            writer.write_all(&(*offset as u64).to_le_bytes())?;
+            writer.write_all(&(*count as u64).to_le_bytes())?;
+        }
+
+        writer.write_all(&payload)?;
+
+        Ok(())
+    }
+
+    /// Memory-map a file written by `write`, parsing only its (small) header eagerly — the
+    /// payload stays on disk until `contains`/`locate` touch the specific pages they need.
+    pub fn memmap<P: AsRef<Path>>(path: P) -> io::Result<DiskTree> {
+        let file = File::open(&path)?;
+        let mmap = unsafe { MmapOptions::new().map(&file)? };
+
+        let mut cursor = &mmap[..];
+
+        let mut magic = [0u8; 4];
+        cursor.read_exact(&mut magic)?;
+        if magic != DISK_TREE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a disk tree file"));
+        }
+
+        let mut version_buf = [0u8; 4];
+        cursor.read_exact(&mut version_buf)?;
+        if u32::from_le_bytes(version_buf) != DISK_TREE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported disk tree version",
+            ));
+        }
+
+        let mut origin = [0 as PointType; 3];
+        for coordinate in origin.iter_mut() {
+            let mut buf = [0u8; std::mem::size_of::<PointType>()];
+            cursor.read_exact(&mut buf)?;
+            *coordinate = PointType::from_le_bytes(buf);
+        }
+        let mut diameter = [0 as PointType; 3];
+        for coordinate in diameter.iter_mut() {
+            let mut buf = [0u8; std::mem::size_of::<PointType>()];
+            cursor.read_exact(&mut buf)?;
+            *coordinate = PointType::from_le_bytes(buf);
+        }
+        let domain = Domain { origin, diameter };
+
+        let mut key_count_buf = [0u8; 8];
+        cursor.read_exact(&mut key_count_buf)?;
+        let key_count = u64::from_le_bytes(key_count_buf) as usize;
+
+        let mut block_count_buf = [0u8; 8];
+        cursor.read_exact(&mut block_count_buf)?;
+        let block_count = u64::from_le_bytes(block_count_buf) as usize;
+
+        let mut blocks = Vec::with_capacity(block_count);
+        for _ in 0..block_count {
+            let mut first_morton_buf = [0u8; 8];
+            cursor.read_exact(&mut first_morton_buf)?;
+            let mut offset_buf = [0u8; 8];
+            cursor.read_exact(&mut offset_buf)?;
+            let mut count_buf = [0u8; 8];
+            cursor.read_exact(&mut count_buf)?;
+
+            blocks.push(BlockEntry {
+                first_morton: u64::from_le_bytes(first_morton_buf),
+                offset: u64::from_le_bytes(offset_buf) as usize,
+                count: u64::from_le_bytes(count_buf) as usize,
+            });
+        }
+
+        let payload_start = mmap.len() - cursor.len();
+
+        Ok(DiskTree {
+            mmap,
+            domain,
+            key_count,
+            blocks,
+            payload_start,
+        })
+    }
+
+    /// This tree's key count, as recorded in the header (not re-derived from the payload).
+    pub fn len(&self) -> usize {
+        self.key_count
+    }
+
+    /// Delta-decode every key held by block `index`.
+    fn decode_block(&self, index: usize) -> Vec<MortonKey> {
+        let block = &self.blocks[index];
+        let mut cursor = &self.mmap[self.payload_start + block.offset..];
+
+        let mut keys = Vec::with_capacity(block.count);
+        let mut previous = 0u64;
+        for _ in 0..block.count {
+            let delta = read_varint(&mut cursor).expect("disk tree payload is truncated");
+            let morton = previous.wrapping_add(delta);
+            keys.push(MortonKey::from_morton(morton));
+            previous = morton;
+        }
+
+        keys
+    }
+
+    /// The index of the one block that could hold `morton`, found by binary-searching each
+    /// block's first key rather than scanning the whole payload. `None` if `morton` precedes
+    /// every key in the tree.
+    fn block_for(&self, morton: u64) -> Option<usize> {
+        match self.blocks.partition_point(|block| block.first_morton <= morton) {
+            0 => None,
+            index => Some(index - 1),
+        }
+    }
+
+    /// Whether `key` is present in the on-disk set.
+    pub fn contains(&self, key: &MortonKey) -> bool {
+        match self.block_for(key.morton()) {
+            Some(index) => self
+                .decode_block(index)
+                .iter()
+                .any(|other| other.morton() == key.morton()),
+            None => false,
+        }
+    }
+
+    /// The on-disk key whose box contains `point`, or `None` if no on-disk key covers it.
+    ///
+    /// Encodes `point` to its finest possible key under `domain`, binary-searches to the block
+    /// that could hold its predecessor the same way `contains` does, then checks whether that
+    /// predecessor (the last key at or before the query in Morton order) covers `point`. Only
+    /// that one key is checked; other keys in the block are not.
+    pub fn locate(&self, point: [PointType; 3]) -> Option<MortonKey> {
+        let query = MortonKey::from_point(&point, &self.domain);
+
+        let index = self.block_for(query.morton())?;
+        let keys = self.decode_block(index);
+
+        let predecessor = keys
+            .into_iter()
+            .take_while(|key| key.morton() <= query.morton())
+            .last()?;
+
+        if predecessor == query || predecessor.is_ancestor(&query) {
+            Some(predecessor)
+        } else {
+            None
+        }
+    }
+}
+
+/// Magic bytes identifying a file written by `write_container`.
+const COMPRESSED_TREE_MAGIC: [u8; 4] = *b"RTCZ";
+
+/// Target amount of *uncompressed* payload per block written by `write_compressed` before
+/// starting a new one. Unlike `DiskTree`'s fixed key-count blocks, this is sized in bytes so
+/// compression ratio and block count stay comparable across differently-dense Morton ranges.
+const COMPRESSED_BLOCK_BYTES: usize = 64 * 1024;
+
+/// Number of points grouped into a single block by `write_compressed_points`. Points don't
+/// delta-compress the way bare Morton ids do, so (unlike the key blocks) these are sized by
+/// count rather than by estimating serialized bytes up front.
+const COMPRESSED_POINTS_PER_BLOCK: usize = 1024;
+
+/// One block's entry in a `write_container` index: the Morton-id range it covers (so a range
+/// query can skip it without touching its payload), where its compressed bytes start, and
+/// enough to decompress and verify them.
+struct CompressedBlockEntry {
+    min_morton: u64,
+    max_morton: u64,
+    offset: u64,
+    compressed_len: u64,
+    raw_len: u64,
+    checksum: u64,
+}
+
+/// Write a generic block container: a header (magic, codec, block count, each block's Morton
+/// range/offset/length/checksum) followed by the blocks' compressed bytes back to back.
+///
+/// Shared by `write_compressed` (Morton keys) and `write_compressed_points` (points) so both
+/// formats get the same block/codec/checksum/range-index machinery; only how each block's raw
+/// bytes are produced differs between the two. Each block is compressed and checksummed
+/// independently, so `read_compressed_range` can inflate just the blocks whose range overlaps a
+/// query.
+fn write_container<P: AsRef<Path>>(
+    path: P,
+    compression: CompressionType,
+    blocks: &[(u64, u64, Vec<u8>)],
+) -> io::Result<()> {
+    let mut entries = Vec::with_capacity(blocks.len());
+    let mut compressed_payload = Vec::new();
+
+    for (min_morton, max_morton, raw) in blocks {
+        let checksum = xxhash_rust::xxh3::xxh3_64(raw);
+        let compressed = compression.compress(raw);
+        let offset = compressed_payload.len() as u64;
+
+        entries.push(CompressedBlockEntry {
+            min_morton: *min_morton,
+            max_morton: *max_morton,
+            offset,
+            compressed_len: compressed.len() as u64,
+            raw_len: raw.len() as u64,
+            checksum,
+        });
+        compressed_payload.extend_from_slice(&compressed);
+    }
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(&COMPRESSED_TREE_MAGIC)?;
+    writer.write_all(&compression.tag().to_le_bytes())?;
+    writer.write_all(&(entries.len() as u64).to_le_bytes())?;
+    for entry in &entries {
+        writer.write_all(&entry.min_morton.to_le_bytes())?;
+        writer.write_all(&entry.max_morton.to_le_bytes())?;
+        writer.write_all(&entry.offset.to_le_bytes())?;
+        writer.write_all(&entry.compressed_len.to_le_bytes())?;
+        writer.write_all(&entry.raw_len.to_le_bytes())?;
+        writer.write_all(&entry.checksum.to_le_bytes())?;
+    }
+    writer.write_all(&compressed_payload)?;
+
+    Ok(())
+}
+
+/// A container's parsed header: the codec it was written with, every block's index entry, and
+/// where the compressed payload region begins — without touching any block's bytes.
+struct CompressedIndex {
+    compression: CompressionType,
+    entries: Vec<CompressedBlockEntry>,
+    payload_start: u64,
+}
+
+/// Open `path` and parse its header, leaving the file positioned (and its blocks un-read) for
+/// `read_block_raw` to seek into on demand.
+fn read_index<P: AsRef<Path>>(path: P) -> io::Result<(File, CompressedIndex)> {
+    let mut file = File::open(path)?;
+
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    if magic != COMPRESSED_TREE_MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a compressed tree file"));
+    }
+
+    let mut compression_buf = [0u8; 4];
+    file.read_exact(&mut compression_buf)?;
+    let compression = CompressionType::from_tag(u32::from_le_bytes(compression_buf));
+
+    let mut count_buf = [0u8; 8];
+    file.read_exact(&mut count_buf)?;
+    let count = u64::from_le_bytes(count_buf) as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut read_u64 = |file: &mut File| -> io::Result<u64> {
+            let mut buf = [0u8; 8];
+            file.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        };
+
+        entries.push(CompressedBlockEntry {
+            min_morton: read_u64(&mut file)?,
+            max_morton: read_u64(&mut file)?,
+            offset: read_u64(&mut file)?,
+            compressed_len: read_u64(&mut file)?,
+            raw_len: read_u64(&mut file)?,
+            checksum: read_u64(&mut file)?,
+        });
+    }
+
+    let payload_start = file.stream_position()?;
+
+    Ok((file, CompressedIndex { compression, entries, payload_start }))
+}
+
+/// Seek to, decompress and checksum-verify one block's raw bytes.
+fn read_block_raw(
+    file: &mut File,
+    payload_start: u64,
+    entry: &CompressedBlockEntry,
+    compression: CompressionType,
+) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(payload_start + entry.offset))?;
+    let mut compressed = vec![0u8; entry.compressed_len as usize];
+    file.read_exact(&mut compressed)?;
+
+    let raw = compression.decompress(&compressed, entry.raw_len as usize);
+    if xxhash_rust::xxh3::xxh3_64(&raw) != entry.checksum {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "compressed tree block failed its xxh3 checksum",
+        ));
+    }
+
+    Ok(raw)
+}
+
+/// Serialize `keys` (need not already be sorted) to `path` as `write_container`-format,
+/// delta-varint-encoded, byte-sized blocks. As in `DiskTree::write`, each block's deltas
+/// restart from zero rather than chaining off the previous block's last key, so
+/// `read_compressed_range` can decode a block without first decoding every block before it.
+pub fn write_compressed<P: AsRef<Path>>(
+    path: P,
+    keys: &[MortonKey],
+    compression: CompressionType,
+) -> io::Result<()> {
+    let mut sorted = keys.to_vec();
+    sorted.sort_by_key(|key| key.morton());
+
+    let mut blocks = Vec::new();
+    let mut raw = Vec::new();
+    let mut min_morton = 0u64;
+    let mut max_morton = 0u64;
+    let mut previous = 0u64;
+    let mut started = false;
+
+    for key in &sorted {
+        let morton = key.morton();
+        if !started {
+            min_morton = morton;
+            previous = 0;
+            started = true;
+        }
+        write_varint(&mut raw, morton.wrapping_sub(previous))?;
+        previous = morton;
+        max_morton = morton;
+
+        if raw.len() >= COMPRESSED_BLOCK_BYTES {
+            blocks.push((min_morton, max_morton, std::mem::take(&mut raw)));
+            started = false;
+        }
+    }
+    if !raw.is_empty() {
+        blocks.push((min_morton, max_morton, raw));
+    }
+
+    write_container(path, compression, &blocks)
+}
+
+/// Load every key from a file written by `write_compressed`.
+pub fn read_compressed<P: AsRef<Path>>(path: P) -> io::Result<Vec<MortonKey>> {
+    read_compressed_range(path, u64::MIN, u64::MAX)
+}
+
+/// Load only the keys in `[min_morton, max_morton]` from a file written by `write_compressed`,
+/// decompressing just the blocks whose range overlaps the query instead of the whole file.
+pub fn read_compressed_range<P: AsRef<Path>>(
+    path: P,
+    min_morton: u64,
+    max_morton: u64,
+) -> io::Result<Vec<MortonKey>> {
+    let (mut file, index) = read_index(path)?;
+
+    let mut keys = Vec::new();
+    for entry in &index.entries {
+        if entry.max_morton < min_morton || entry.min_morton > max_morton {
+            continue;
+        }
+
+        let raw = read_block_raw(&mut file, index.payload_start, entry, index.compression)?;
+
+        let mut cursor = &raw[..];
+        let mut previous = 0u64;
+        while !cursor.is_empty() {
+            let delta = read_varint(&mut cursor)?;
+            let morton = previous.wrapping_add(delta);
+            previous = morton;
+            if morton >= min_morton && morton <= max_morton {
+                keys.push(MortonKey::from_morton(morton));
+            }
+        }
+    }
+
+    Ok(keys)
+}
+
+/// Serialize `points` (need not already be sorted) to `path` as `write_container`-format
+/// blocks, each JSON-encoded (reusing `JSON`'s `serde_json` dependency, since a `Point`'s
+/// coordinate/global_idx don't share the delta-compressible structure a bare Morton id does)
+/// before compression.
+pub fn write_compressed_points<P: AsRef<Path>>(
+    path: P,
+    points: &[Point],
+    compression: CompressionType,
+) -> io::Result<()> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|point| point.key.morton());
+
+    let mut blocks = Vec::new();
+    for chunk in sorted.chunks(COMPRESSED_POINTS_PER_BLOCK) {
+        let raw = serde_json::to_vec(chunk).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let min_morton = chunk.first().unwrap().key.morton();
+        let max_morton = chunk.last().unwrap().key.morton();
+        blocks.push((min_morton, max_morton, raw));
+    }
+
+    write_container(path, compression, &blocks)
+}
+
+/// Load every point from a file written by `write_compressed_points`.
+pub fn read_compressed_points<P: AsRef<Path>>(path: P) -> io::Result<Vec<Point>> {
+    read_compressed_points_range(path, u64::MIN, u64::MAX)
+}
+
+/// Load only the points whose key's Morton id falls in `[min_morton, max_morton]` from a file
+/// written by `write_compressed_points`, decompressing just the blocks whose range overlaps
+/// the query.
+pub fn read_compressed_points_range<P: AsRef<Path>>(
+    path: P,
+    min_morton: u64,
+    max_morton: u64,
+) -> io::Result<Vec<Point>> {
+    let (mut file, index) = read_index(path)?;
+
+    let mut points = Vec::new();
+    for entry in &index.entries {
+        if entry.max_morton < min_morton || entry.min_morton > max_morton {
+            continue;
+        }
+
+        let raw = read_block_raw(&mut file, index.payload_start, entry, index.compression)?;
+        let chunk: Vec<Point> = serde_json::from_slice(&raw)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        points.extend(chunk.into_iter().filter(|point| {
+            let morton = point.key.morton();
+            morton >= min_morton && morton <= max_morton
+        }));
+    }
+
+    Ok(points)
 }
\ No newline at end of file
@@ -0,0 +1,327 @@
+//! LZ77-style delta encoding between two `DistributedTree` snapshots.
+//!
+//! Refinement and rebalancing usually change only a small fraction of leaves, so checkpointing
+//! the full `keys` array every step (as `write_hdf5` does) is wasteful. `diff_keys` instead
+//! treats the previous snapshot's sorted key stream as an LZ77 dictionary: long runs of `curr`
+//! that already appear in `prev` become a single `Copy { offset, length }` reference into it,
+//! and only genuinely new keys are stored as literals. `write_hdf5_delta`/`read_hdf5_delta` and
+//! `apply_delta` round-trip a `KeyPatch` through HDF5 the same way `write_hdf5`/`read_hdf5`
+//! round-trip a full tree.
+
+use std::collections::HashMap;
+
+use mpi::{datatype::PartitionMut, topology::UserCommunicator, traits::*, Count};
+
+use crate::{
+    distributed::DistributedTree,
+    octree::{read_varint, write_varint},
+    types::morton::MortonKey,
+};
+
+/// One instruction in a `KeyPatch`: either copy a run out of the dictionary (the previous
+/// snapshot's sorted keys) or splice in keys that weren't present there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyOp {
+    /// Copy `length` keys from `base[offset..offset + length]`.
+    Copy { offset: usize, length: usize },
+    /// Keys with no match in the dictionary, taken verbatim.
+    Literal(Vec<MortonKey>),
+}
+
+/// A sequence of `KeyOp`s that replays `prev`'s sorted keys into `curr`'s sorted keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyPatch {
+    pub ops: Vec<KeyOp>,
+}
+
+/// Diff `new` against the dictionary `old`, greedily matching the longest run starting at each
+/// position: look up `new[i]` in an index of `old`'s key positions, extend the match while
+/// `old`/`new` keep agreeing, and emit a `Copy` for it; keys with no match at all accumulate into
+/// a `Literal` run instead. Since a linearized tree's keys are unique, `old`'s index only needs
+/// to remember one position per key.
+pub fn diff_keys(old: &[MortonKey], new: &[MortonKey]) -> KeyPatch {
+    let index: HashMap<MortonKey, usize> = old
+        .iter()
+        .enumerate()
+        .map(|(i, key)| (*key, i))
+        .collect();
+
+    let mut ops = Vec::new();
+    let mut literal_run: Vec<MortonKey> = Vec::new();
+    let mut i = 0;
+
+    while i < new.len() {
+        let matched = index.get(&new[i]).and_then(|&offset| {
+            let mut length = 0;
+            while offset + length < old.len()
+                && i + length < new.len()
+                && old[offset + length] == new[i + length]
+            {
+                length += 1;
+            }
+            if length > 0 {
+                Some((offset, length))
+            } else {
+                None
+            }
+        });
+
+        match matched {
+            Some((offset, length)) => {
+                if !literal_run.is_empty() {
+                    ops.push(KeyOp::Literal(std::mem::take(&mut literal_run)));
+                }
+                ops.push(KeyOp::Copy { offset, length });
+                i += length;
+            }
+            None => {
+                literal_run.push(new[i]);
+                i += 1;
+            }
+        }
+    }
+
+    if !literal_run.is_empty() {
+        ops.push(KeyOp::Literal(literal_run));
+    }
+
+    KeyPatch { ops }
+}
+
+/// Replay `patch` against `base` to reconstruct the keys it was diffed against, the inverse of
+/// `diff_keys(base, reconstructed)`.
+pub fn apply_delta(base: &[MortonKey], patch: &KeyPatch) -> Vec<MortonKey> {
+    let mut keys = Vec::new();
+    for op in &patch.ops {
+        match op {
+            KeyOp::Copy { offset, length } => {
+                keys.extend_from_slice(&base[*offset..*offset + *length]);
+            }
+            KeyOp::Literal(literal) => keys.extend_from_slice(literal),
+        }
+    }
+    keys
+}
+
+/// Tag byte distinguishing a `Copy` op from a `Literal` op in the encoded patch stream.
+const OP_COPY: u64 = 0;
+const OP_LITERAL: u64 = 1;
+
+/// Serialize `patch` as a varint stream: each op is a tag followed by its payload, with
+/// `Literal` keys delta-encoded the same way `DistributedTree::encode_keys` encodes a full key
+/// array, so a long literal run compresses about as well as it would have outside a patch.
+fn encode_patch(patch: &KeyPatch) -> Vec<u8> {
+    let mut payload = Vec::new();
+    for op in &patch.ops {
+        match op {
+            KeyOp::Copy { offset, length } => {
+                write_varint(&mut payload, OP_COPY).unwrap();
+                write_varint(&mut payload, *offset as u64).unwrap();
+                write_varint(&mut payload, *length as u64).unwrap();
+            }
+            KeyOp::Literal(keys) => {
+                write_varint(&mut payload, OP_LITERAL).unwrap();
+                write_varint(&mut payload, keys.len() as u64).unwrap();
+                let mut previous = 0u64;
+                for key in keys {
+                    let morton = key.morton();
+                    write_varint(&mut payload, morton.wrapping_sub(previous)).unwrap();
+                    previous = morton;
+                }
+            }
+        }
+    }
+    payload
+}
+
+/// Inverse of `encode_patch`.
+fn decode_patch(payload: &[u8]) -> KeyPatch {
+    let mut cursor = payload;
+    let mut ops = Vec::new();
+    while !cursor.is_empty() {
+        match read_varint(&mut cursor).unwrap() {
+            OP_COPY => {
+                let offset = read_varint(&mut cursor).unwrap() as usize;
+                let length = read_varint(&mut cursor).unwrap() as usize;
+                ops.push(KeyOp::Copy { offset, length });
+            }
+            OP_LITERAL => {
+                let nkeys = read_varint(&mut cursor).unwrap() as usize;
+                let mut keys = Vec::with_capacity(nkeys);
+                let mut previous = 0u64;
+                for _ in 0..nkeys {
+                    let delta = read_varint(&mut cursor).unwrap();
+                    let morton = previous.wrapping_add(delta);
+                    keys.push(MortonKey::from_morton(morton));
+                    previous = morton;
+                }
+                ops.push(KeyOp::Literal(keys));
+            }
+            other => panic!("unknown key patch op tag {}", other),
+        }
+    }
+    KeyPatch { ops }
+}
+
+/// Gather `tree`'s local keys into a single sorted global array on the root rank, the same
+/// collective `write_hdf5` uses to assemble the full checkpoint; returns `None` off the root.
+fn gather_sorted_keys(world: &UserCommunicator, tree: &DistributedTree) -> Option<Vec<MortonKey>> {
+    let comm = world.duplicate();
+    let rank = comm.rank();
+    let size = comm.size();
+
+    let root_rank = 0;
+    let root_process = comm.process_at_rank(root_rank);
+
+    let nlocal_keys: Count = tree.keys.len() as Count;
+    let mut global_key_counts: Vec<Count> = vec![0; size as usize];
+
+    if rank == root_rank {
+        root_process.gather_into_root(&nlocal_keys, &mut global_key_counts[..]);
+    } else {
+        root_process.gather_into(&nlocal_keys);
+    }
+
+    if rank == root_rank {
+        let global_key_displs: Vec<Count> = global_key_counts
+            .iter()
+            .scan(0, |acc, &x| {
+                let tmp = *acc;
+                *acc += x;
+                Some(tmp)
+            })
+            .collect();
+
+        let global_key_count: usize = global_key_counts.iter().sum::<Count>() as usize;
+        let mut global_keys: Vec<MortonKey> = vec![MortonKey::default(); global_key_count];
+
+        let mut key_partition =
+            PartitionMut::new(&mut global_keys[..], global_key_counts, &global_key_displs[..]);
+        root_process.gather_varcount_into_root(&tree.keys[..], &mut key_partition);
+
+        global_keys.sort();
+        Some(global_keys)
+    } else {
+        root_process.gather_varcount_into(&tree.keys[..]);
+        None
+    }
+}
+
+impl DistributedTree {
+    /// Checkpoint the difference between `prev` and `curr` to `{filename}.hdf5` instead of
+    /// dumping `curr`'s full key set: both snapshots' keys are gathered and sorted on the root
+    /// rank, diffed with `diff_keys` (dictionary = `prev`'s keys), and the resulting `KeyPatch`
+    /// is encoded and written as a single byte dataset, with the old/new key counts stored as
+    /// `base_len`/`new_len` attributes (`read_hdf5_delta` and `apply_delta` do not read them).
+    pub fn write_hdf5_delta(
+        world: &UserCommunicator,
+        filename: String,
+        prev: &DistributedTree,
+        curr: &DistributedTree,
+    ) -> hdf5::Result<()> {
+        let comm = world.duplicate();
+        let rank = comm.rank();
+
+        let global_prev_keys = gather_sorted_keys(&comm, prev);
+        let global_curr_keys = gather_sorted_keys(&comm, curr);
+
+        if rank == 0 {
+            let global_prev_keys = global_prev_keys.unwrap();
+            let global_curr_keys = global_curr_keys.unwrap();
+
+            let patch = diff_keys(&global_prev_keys, &global_curr_keys);
+            let payload = encode_patch(&patch);
+
+            let file = hdf5::File::create(format!("{}.hdf5", filename))?;
+
+            let data = file
+                .new_dataset::<u8>()
+                .shape([payload.len()])
+                .create("patch")?;
+            data.write_raw(&payload)?;
+
+            file.new_attr::<u64>()
+                .create("base_len")?
+                .write_scalar(&(global_prev_keys.len() as u64))?;
+            file.new_attr::<u64>()
+                .create("new_len")?
+                .write_scalar(&(global_curr_keys.len() as u64))?;
+        }
+
+        Ok(())
+    }
+
+    /// Load the `KeyPatch` written by `write_hdf5_delta`, to hand to `apply_delta` alongside the
+    /// previous snapshot's sorted global keys.
+    pub fn read_hdf5_delta(filepath: String) -> hdf5::Result<KeyPatch> {
+        let file = hdf5::File::open(&filepath)?;
+        let payload: Vec<u8> = file.dataset("patch")?.read_raw::<u8>()?;
+        Ok(decode_patch(&payload))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+    use rand::SeedableRng;
+
+    fn random_sorted_keys(rng: &mut StdRng, n: usize) -> Vec<MortonKey> {
+        let mut keys: Vec<MortonKey> = (0..n)
+            .map(|_| MortonKey::from_morton(rng.gen::<u32>() as u64))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    #[test]
+    fn test_apply_delta_inverts_diff_keys() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..50 {
+            let old = random_sorted_keys(&mut rng, 40);
+            let new = random_sorted_keys(&mut rng, 40);
+
+            let patch = diff_keys(&old, &new);
+            let reconstructed = apply_delta(&old, &patch);
+
+            assert_eq!(reconstructed, new);
+        }
+    }
+
+    #[test]
+    fn test_diff_keys_against_identical_dictionary_is_one_copy() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let keys = random_sorted_keys(&mut rng, 40);
+
+        let patch = diff_keys(&keys, &keys);
+
+        assert_eq!(patch.ops, vec![KeyOp::Copy { offset: 0, length: keys.len() }]);
+    }
+
+    #[test]
+    fn test_diff_keys_against_empty_dictionary_is_all_literal() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let keys = random_sorted_keys(&mut rng, 10);
+
+        let patch = diff_keys(&[], &keys);
+
+        assert_eq!(patch.ops, vec![KeyOp::Literal(keys.clone())]);
+        assert_eq!(apply_delta(&[], &patch), keys);
+    }
+
+    #[test]
+    fn test_encode_decode_patch_round_trip() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let old = random_sorted_keys(&mut rng, 40);
+        let new = random_sorted_keys(&mut rng, 40);
+
+        let patch = diff_keys(&old, &new);
+        let decoded = decode_patch(&encode_patch(&patch));
+
+        assert_eq!(decoded, patch);
+        assert_eq!(apply_delta(&old, &decoded), new);
+    }
+}
@@ -2,8 +2,11 @@
 
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
 
 use mpi::{
+    collective::SystemOperation,
     datatype::{Partition, PartitionMut},
     topology::{Rank, UserCommunicator},
     traits::*,
@@ -13,8 +16,12 @@ use mpi::{
 use hyksort::hyksort::hyksort;
 
 use crate::{
+    comm::{CommEngine, SyncCommEngine},
     constants::{K, NCRIT, ROOT},
+    curve::{Morton, SpaceFillingCurve},
     data::{HDF5, JSON, VTK},
+    octree::{read_varint, write_varint, CompressionType},
+    rmq::LcpIndex,
     single_node::Tree,
     types::{
         domain::Domain,
@@ -39,19 +46,49 @@ pub struct DistributedTree {
 
     /// Domain spanned by the points in the tree.
     pub domain: Domain,
+
+    /// The space-filling curve used to order leaves during partitioning.
+    pub curve: Box<dyn SpaceFillingCurve>,
+
+    /// The coarse seed octants that bound each rank's partition, cached from construction so
+    /// `insert_points`/`remove_points` can route points to their owning rank without
+    /// recomputing them from scratch.
+    pub seeds: Vec<MortonKey>,
 }
 
 impl DistributedTree {
-    /// Create a new DistributedTree from a set of distributed points which define a domain.
+    /// Create a new DistributedTree from a set of distributed points which define a domain,
+    /// ordering leaves with a Morton (Z-order) curve.
     pub fn new(
         points: &[[PointType; 3]],
         balanced: bool,
         world: &UserCommunicator,
+    ) -> DistributedTree {
+        DistributedTree::new_with_curve(points, balanced, world, Box::new(Morton), &SyncCommEngine)
+    }
+
+    /// Create a new DistributedTree, ordering leaves with the given `SpaceFillingCurve` and
+    /// redistributing boundary points with the given `CommEngine`.
+    ///
+    /// A `Hilbert` curve gives strictly better spatial locality than the default `Morton`
+    /// ordering, which reduces the number of MPI neighbours each rank's block has after
+    /// partitioning. `engine` carries the boundary point hand-off in
+    /// `transfer_points_to_blocktree`: an `AsyncCommEngine` posts it as non-blocking chunks and
+    /// waits on them together, `SyncCommEngine` as one blocking round trip. Either way the
+    /// hand-off has completed before construction continues, and the hyksort passes do not use
+    /// the engine.
+    pub fn new_with_curve(
+        points: &[[PointType; 3]],
+        balanced: bool,
+        world: &UserCommunicator,
+        curve: Box<dyn SpaceFillingCurve>,
+        engine: &dyn CommEngine,
     ) -> DistributedTree {
         let domain = Domain::from_global_points(&points, world);
 
         if balanced {
-            let (points, points_to_keys) = DistributedTree::balanced_tree(world, points, &domain);
+            let (points, points_to_keys, seeds) =
+                DistributedTree::balanced_tree(world, points, &domain, curve.as_ref(), engine);
             let keys = points.iter().map(|p| p.key).collect();
 
             DistributedTree {
@@ -60,9 +97,12 @@ impl DistributedTree {
                 keys,
                 points_to_keys,
                 domain,
+                curve,
+                seeds,
             }
         } else {
-            let (points, points_to_keys) = DistributedTree::unbalanced_tree(world, points, &domain);
+            let (points, points_to_keys, seeds) =
+                DistributedTree::unbalanced_tree(world, points, &domain, curve.as_ref(), engine);
             let keys = points.iter().map(|p| p.key).collect();
 
             DistributedTree {
@@ -71,6 +111,8 @@ impl DistributedTree {
                 keys,
                 points_to_keys,
                 domain,
+                curve,
+                seeds,
             }
         }
     }
@@ -212,24 +254,297 @@ impl DistributedTree {
         DistributedTree::assign_nodes_to_leaves(leaves, &split_blocktree)
     }
 
-    /// Find the seeds, defined as coarsest leaf/leaves, at each processor [1].
-    fn find_seeds(leaves: &[MortonKey]) -> Vec<MortonKey> {
-        let min: MortonKey = *leaves.iter().min().unwrap();
-        let max: MortonKey = *leaves.iter().max().unwrap();
-
-        // Complete the region between the least and greatest leaves.
-        let mut complete = Tree::complete_region(&min, &max);
-        complete.push(min);
-        complete.push(max);
-
-        // Find seeds by filtering for leaves at coarsest level
-        let coarsest_level = complete.iter().map(|k| k.level()).min().unwrap();
-        let mut seeds: Vec<MortonKey> = complete
-            .into_iter()
-            .filter(|k| k.level() == coarsest_level)
+    /// Merge sibling blocks whose combined particle count falls under `NCRIT` back into their
+    /// parent. The mirror image of `split_blocks`, used by `remove_points` to coarsen blocks
+    /// that become underfull after points are deleted.
+    fn merge_blocks(
+        leaves: &Vec<MortonKey>,
+        mut blocktree: Vec<MortonKey>,
+    ) -> HashMap<MortonKey, MortonKey> {
+        loop {
+            let blocks_to_leaves = DistributedTree::assign_nodes_to_leaves(leaves, &blocktree);
+
+            let mut blocks_to_npoints: HashMap<MortonKey, usize> = HashMap::new();
+            for (_, block) in blocks_to_leaves {
+                *blocks_to_npoints.entry(block).or_insert(0) += 1;
+            }
+
+            // Group blocks by parent so full sibling sets can be considered for merging.
+            let mut parents_to_children: HashMap<MortonKey, Vec<MortonKey>> = HashMap::new();
+            for &block in blocks_to_npoints.keys() {
+                if block.level() > 0 {
+                    parents_to_children
+                        .entry(block.parent())
+                        .or_insert_with(Vec::new)
+                        .push(block);
+                }
+            }
+
+            let mergeable: Vec<MortonKey> = parents_to_children
+                .iter()
+                .filter(|(_, children)| {
+                    children.len() == 8
+                        && children.iter().map(|c| blocks_to_npoints[c]).sum::<usize>() <= NCRIT
+                })
+                .map(|(&parent, _)| parent)
+                .collect();
+
+            if mergeable.is_empty() {
+                break DistributedTree::assign_nodes_to_leaves(leaves, &blocktree);
+            }
+
+            let absorbed: HashSet<MortonKey> = mergeable
+                .iter()
+                .flat_map(|parent| parent.children())
+                .collect();
+
+            blocktree = blocks_to_npoints
+                .keys()
+                .filter(|b| !absorbed.contains(b))
+                .cloned()
+                .chain(mergeable)
+                .collect();
+        }
+    }
+
+    /// Distributed 2:1 balance across MPI ranks.
+    ///
+    /// `Tree::balance` only enforces level balance within a single rank's keys, so a 2:1
+    /// violation straddling a rank boundary (introduced by `unbalanced_tree`'s `hyksort`
+    /// partitioning) is invisible to it. Each round runs the local ripple-balance, then
+    /// exchanges a ghost layer — each rank's boundary octant and its same-level neighbors —
+    /// with `previous_process`/`next_process`, the same partner-send pattern
+    /// `complete_blocktree`/`transfer_points_to_blocktree` use, merges the received ghosts in,
+    /// and repeats. Convergence (no rank inserted any new octant this round) is detected with
+    /// an `MPI_Allreduce` over each round's insertion count, after which `linearize` drops the
+    /// duplicates and subsumed ancestors the ghost merges leave behind.
+    pub fn balance_distributed(keys: &[MortonKey], world: &UserCommunicator) -> Tree {
+        let rank = world.rank();
+        let size = world.size();
+
+        let next_rank = if rank + 1 < size { rank + 1 } else { 0 };
+        let previous_rank = if rank > 0 { rank - 1 } else { size - 1 };
+
+        let mut local = Tree {
+            keys: keys.to_vec(),
+        };
+
+        loop {
+            let nbefore = local.keys.len();
+            local = local.balance();
+
+            if size > 1 {
+                let local_set: HashSet<MortonKey> = local.keys.iter().cloned().collect();
+                let min = *local.keys.iter().min().unwrap();
+                let max = *local.keys.iter().max().unwrap();
+
+                // This rank's boundary octant, plus whichever of its same-level neighbors this
+                // rank actually holds, is all the adjacent rank needs to catch a 2:1 violation
+                // straddling the shared boundary.
+                let ghost_for_previous: Vec<MortonKey> = std::iter::once(min)
+                    .chain(min.neighbors().into_iter().filter(|n| local_set.contains(n)))
+                    .collect();
+                let ghost_for_next: Vec<MortonKey> = std::iter::once(max)
+                    .chain(max.neighbors().into_iter().filter(|n| local_set.contains(n)))
+                    .collect();
+
+                let previous_process = world.process_at_rank(previous_rank);
+                let next_process = world.process_at_rank(next_rank);
+
+                let nsend_previous = ghost_for_previous.len() as Count;
+                previous_process.send(&nsend_previous);
+                previous_process.send(&ghost_for_previous[..]);
+
+                let nsend_next = ghost_for_next.len() as Count;
+                next_process.send(&nsend_next);
+                next_process.send(&ghost_for_next[..]);
+
+                let mut nrecv_next = 0 as Count;
+                next_process.receive_into(&mut nrecv_next);
+                let mut from_next = vec![MortonKey::default(); nrecv_next as usize];
+                next_process.receive_into(&mut from_next[..]);
+
+                let mut nrecv_previous = 0 as Count;
+                previous_process.receive_into(&mut nrecv_previous);
+                let mut from_previous = vec![MortonKey::default(); nrecv_previous as usize];
+                previous_process.receive_into(&mut from_previous[..]);
+
+                local.keys.extend(from_next);
+                local.keys.extend(from_previous);
+            }
+
+            local.sort();
+            local.keys.dedup();
+
+            let inserted = (local.keys.len() - nbefore) as i32;
+            let mut total_inserted = 0i32;
+            world.all_reduce_into(&inserted, &mut total_inserted, SystemOperation::sum());
+
+            if total_inserted == 0 {
+                break;
+            }
+        }
+
+        local.linearize();
+        local
+    }
+
+    /// Gather every rank's minimum local key into a genuine global, rank-indexed boundary
+    /// table: `starts[r]` is the smallest key rank `r` currently owns. Mirrors
+    /// `TreeLayout::from_tree`'s `all_gather_into` of each rank's interval start, since
+    /// `unbalanced_tree`/`balanced_tree` already leave each rank holding a contiguous,
+    /// increasing slice of the global key range.
+    fn rank_starts(world: &UserCommunicator, local_keys: &[MortonKey]) -> Vec<MortonKey> {
+        let comm = world.duplicate();
+        let size = comm.size();
+
+        let local_start = *local_keys.iter().min().unwrap();
+        let mut starts = vec![MortonKey::default(); size as usize];
+        comm.all_gather_into(&local_start, &mut starts[..]);
+        starts
+    }
+
+    /// Route a key to the rank owning its spatial region, by locating the interval of `starts`
+    /// (a genuine global, rank-indexed table from `rank_starts`, one entry per rank) it falls
+    /// into.
+    fn owning_rank(starts: &[MortonKey], key: &MortonKey, size: Rank) -> Rank {
+        let rank = match starts.binary_search(key) {
+            Ok(idx) => idx,
+            Err(idx) => idx.saturating_sub(1),
+        };
+        (rank as Rank).min(size - 1)
+    }
+
+    /// Insert new points into an existing tree without a full rebuild.
+    ///
+    /// Each new point is routed to the rank owning its spatial region by comparing its encoded
+    /// key against `rank_starts`, a global table of every rank's minimum local key gathered
+    /// fresh via `all_gather_into` (the same collective `TreeLayout::from_tree` uses), then only
+    /// the blocks whose occupancy now exceeds `NCRIT` are re-split via `split_blocks`, rather
+    /// than rebuilding the whole tree from scratch. Afterwards, this rank's own cached `seeds`
+    /// are recomputed locally from the updated `keys` via `find_seeds`, the same call
+    /// `layout::TreeLayout::rebalance` makes after migrating points across a boundary, rather
+    /// than re-running `complete_blocktree`'s global exchange over the whole seed set.
+    pub fn insert_points(&mut self, world: &UserCommunicator, new_points: &[[PointType; 3]]) {
+        let rank = world.rank();
+        let size = world.size();
+
+        let offset = self.points.len();
+        let encoded: Vec<Point> = new_points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Point {
+                coordinate: *p,
+                global_idx: offset + i,
+                key: MortonKey::from_point(p, &self.domain),
+            })
             .collect();
 
-        seeds.sort();
+        let starts = DistributedTree::rank_starts(world, &self.keys);
+
+        // Bucket the new points by the rank whose interval in `starts` contains them.
+        let mut outgoing: Vec<Vec<Point>> = vec![Vec::new(); size as usize];
+        for point in encoded {
+            let owner = DistributedTree::owning_rank(&starts, &point.key, size);
+            outgoing[owner as usize].push(point);
+        }
+
+        let mut incoming: Vec<Point> = outgoing[rank as usize].drain(..).collect();
+
+        for other in 0..size {
+            if other == rank {
+                continue;
+            }
+            let msg = &outgoing[other as usize];
+            let msg_size = msg.len() as Rank;
+            world.process_at_rank(other).send(&msg_size);
+            if msg_size > 0 {
+                world.process_at_rank(other).send(&msg[..]);
+            }
+        }
+
+        for other in 0..size {
+            if other == rank {
+                continue;
+            }
+            let mut incoming_size: Rank = 0;
+            world.process_at_rank(other).receive_into(&mut incoming_size);
+            if incoming_size > 0 {
+                let mut buffer = vec![Point::default(); incoming_size as usize];
+                world.process_at_rank(other).receive_into(&mut buffer[..]);
+                incoming.append(&mut buffer);
+            }
+        }
+
+        self.points.append(&mut incoming);
+        self.points.sort();
+
+        // Re-split only the blocks whose occupancy crossed NCRIT.
+        let leaves: Vec<MortonKey> = self.points.iter().map(|p| p.key).collect();
+        let blocktree: Vec<MortonKey> = self.keys.iter().cloned().collect::<HashSet<_>>().into_iter().collect();
+        self.points_to_keys = DistributedTree::split_blocks(&leaves, blocktree);
+
+        for point in self.points.iter_mut() {
+            point.key = *self.points_to_keys.get(&point.key).unwrap();
+        }
+        self.keys = self.points.iter().map(|p| p.key).collect();
+
+        // Refresh this rank's own cached coarse seeds from the now-updated local keys, the same
+        // local recompute `layout::TreeLayout::rebalance` does after points migrate across a
+        // rank boundary — cheaper than re-running `complete_blocktree`'s global exchange, since
+        // `find_seeds` only ever looks at this rank's own completed key set. Unlike the old
+        // neighbor-boundary-mutation it replaces, this doesn't silently assume `self.seeds` is a
+        // globally rank-indexed table (it never was — see `rank_starts`/`owning_rank` above).
+        self.seeds = DistributedTree::find_seeds(&self.keys, self.curve.as_ref());
+    }
+
+    /// Remove points from an existing tree by their `global_idx`, without a full rebuild.
+    ///
+    /// After deletion, sibling blocks whose combined occupancy falls under `NCRIT` are merged
+    /// back into their parent via `merge_blocks`, the local analogue of `split_blocks`.
+    pub fn remove_points(&mut self, global_indices: &[usize]) {
+        let removed: HashSet<usize> = global_indices.iter().cloned().collect();
+        self.points.retain(|p| !removed.contains(&p.global_idx));
+
+        let leaves: Vec<MortonKey> = self.points.iter().map(|p| p.key).collect();
+        let blocktree: Vec<MortonKey> = self.keys.iter().cloned().collect::<HashSet<_>>().into_iter().collect();
+        self.points_to_keys = DistributedTree::merge_blocks(&leaves, blocktree);
+
+        for point in self.points.iter_mut() {
+            point.key = *self.points_to_keys.get(&point.key).unwrap();
+        }
+        self.keys = self.points.iter().map(|p| p.key).collect();
+    }
+
+    /// Remove points from an existing tree by the `MortonKey` leaf they currently occupy,
+    /// rather than by `global_idx`, e.g. when the caller tracks points by their spatial key
+    /// instead of their original insertion index. Otherwise identical to `remove_points`.
+    pub fn remove_points_by_key(&mut self, keys: &[MortonKey]) {
+        let removed: HashSet<MortonKey> = keys.iter().cloned().collect();
+        self.points.retain(|p| !removed.contains(&p.key));
+
+        let leaves: Vec<MortonKey> = self.points.iter().map(|p| p.key).collect();
+        let blocktree: Vec<MortonKey> = self.keys.iter().cloned().collect::<HashSet<_>>().into_iter().collect();
+        self.points_to_keys = DistributedTree::merge_blocks(&leaves, blocktree);
+
+        for point in self.points.iter_mut() {
+            point.key = *self.points_to_keys.get(&point.key).unwrap();
+        }
+        self.keys = self.points.iter().map(|p| p.key).collect();
+    }
+
+    /// Find the seeds, defined as coarsest leaf/leaves, at each processor [1].
+    ///
+    /// Built fresh from `leaves` every call via an `LcpIndex`, so the range-minima it derives the
+    /// seeds from never go stale — there's no cached index for a `linearize` to invalidate.
+    ///
+    /// `pub(crate)` so `layout::TreeLayout`'s `rebalance` can recompute a rank's seeds after it
+    /// migrates points across a shifted boundary.
+    pub(crate) fn find_seeds(leaves: &[MortonKey], curve: &dyn SpaceFillingCurve) -> Vec<MortonKey> {
+        let index = LcpIndex::build(leaves);
+        let mut seeds = index.seeds();
+
+        seeds.sort_by_key(|k| curve.sort_key(k));
         seeds
     }
 
@@ -240,6 +555,7 @@ impl DistributedTree {
         seeds: &[MortonKey],
         &rank: &Rank,
         &size: &Rank,
+        engine: &dyn CommEngine,
     ) -> Vec<Point> {
         let mut received_points: Vec<Point> = Vec::new();
 
@@ -261,18 +577,11 @@ impl DistributedTree {
                 .cloned()
                 .collect();
 
-            let msg_size: Rank = msg.len() as Rank;
-            world.process_at_rank(prev_rank).send(&msg_size);
-            world.process_at_rank(prev_rank).send(&msg[..]);
+            engine.send_points(world, prev_rank, &msg);
         }
 
         if rank < (size - 1) {
-            let mut bufsize = 0;
-            world.process_at_rank(next_rank).receive_into(&mut bufsize);
-            let mut buffer = vec![Point::default(); bufsize as usize];
-            world
-                .process_at_rank(next_rank)
-                .receive_into(&mut buffer[..]);
+            let mut buffer = engine.receive_points(world, next_rank);
             received_points.append(&mut buffer);
         }
 
@@ -294,7 +603,9 @@ impl DistributedTree {
         world: &UserCommunicator,
         points: &[[PointType; 3]],
         domain: &Domain,
-    ) -> (Vec<Point>, HashMap<MortonKey, MortonKey>) {
+        curve: &dyn SpaceFillingCurve,
+        engine: &dyn CommEngine,
+    ) -> (Vec<Point>, HashMap<MortonKey, MortonKey>, Vec<MortonKey>) {
         let rank = world.rank();
         let size = world.size();
 
@@ -325,19 +636,20 @@ impl DistributedTree {
         tree.complete();
 
         // 5. Find seeds and compute the coarse blocktree
-        let mut seeds = DistributedTree::find_seeds(&tree.keys);
+        let mut seeds = DistributedTree::find_seeds(&tree.keys, curve);
 
         let blocktree = DistributedTree::complete_blocktree(&mut seeds, &rank, &size, world);
 
         // 5.ii any data below the min seed sent to partner process
-        let points =
-            DistributedTree::transfer_points_to_blocktree(world, &points, &seeds, &rank, &size);
+        let points = DistributedTree::transfer_points_to_blocktree(
+            world, &points, &seeds, &rank, &size, engine,
+        );
 
         // 6. Refine blocks based on ncrit
         let map =
             DistributedTree::split_blocks(&points.iter().map(|p| p.key).collect(), blocktree.keys);
 
-        (points, map)
+        (points, map, seeds)
     }
 
     /// Specialization for balanced trees.
@@ -345,7 +657,9 @@ impl DistributedTree {
         world: &UserCommunicator,
         points: &[[PointType; 3]],
         domain: &Domain,
-    ) -> (Vec<Point>, HashMap<MortonKey, MortonKey>) {
+        curve: &dyn SpaceFillingCurve,
+        engine: &dyn CommEngine,
+    ) -> (Vec<Point>, HashMap<MortonKey, MortonKey>, Vec<MortonKey>) {
         // Create a distributed unbalanced tree;
         let rank = world.rank();
         let size = world.size();
@@ -377,13 +691,14 @@ impl DistributedTree {
         tree.complete();
 
         // 5.i Find seeds and compute the coarse blocktree
-        let mut seeds = DistributedTree::find_seeds(&tree.keys);
+        let mut seeds = DistributedTree::find_seeds(&tree.keys, curve);
 
         let blocktree = DistributedTree::complete_blocktree(&mut seeds, &rank, &size, world);
 
         // 5.ii Send data below the min seed sent to partner process
-        let points =
-            DistributedTree::transfer_points_to_blocktree(world, &points, &seeds, &rank, &size);
+        let points = DistributedTree::transfer_points_to_blocktree(
+            world, &points, &seeds, &rank, &size, engine,
+        );
 
         // 6. Refine blocks based on ncrit
         let unbalanced_tree =
@@ -429,7 +744,152 @@ impl DistributedTree {
             &tree.keys,
         );
 
-        (points, map)
+        (points, map, seeds)
+    }
+
+    /// Persist this rank's share of the tree to `path`, one file per rank (suffixed with the
+    /// rank index), using `Tree`'s delta-encoded, block-compressed key codec for `keys` and an
+    /// uncompressed, fixed-width little-endian layout (coordinates, then `global_idx`) for the
+    /// paired `points`.
+    ///
+    /// This is a lightweight alternative to `write_hdf5` for checkpointing a run without going
+    /// through HDF5's collective IO, at the cost of not gathering onto a single file.
+    pub fn save(&self, path: &str, rank: Rank, compression: CompressionType) -> io::Result<()> {
+        let tree = Tree {
+            keys: self.keys.clone(),
+        };
+        tree.save(format!("{}.rank{}.keys", path, rank), compression)?;
+
+        let file = File::create(format!("{}.rank{}.points", path, rank))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&(self.points.len() as u64).to_le_bytes())?;
+        for point in &self.points {
+            for coordinate in point.coordinate {
+                writer.write_all(&coordinate.to_le_bytes())?;
+            }
+            writer.write_all(&(point.global_idx as u64).to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Load a rank's share of a tree previously written by `save`.
+    ///
+    /// `points_to_keys` is rebuilt by re-running `assign_nodes_to_leaves` over the loaded
+    /// points and keys, rather than being persisted, since it is cheap to recompute.
+    pub fn load(path: &str, rank: Rank, domain: Domain) -> io::Result<DistributedTree> {
+        let tree = Tree::load(format!("{}.rank{}.keys", path, rank))?;
+
+        let file = File::open(format!("{}.rank{}.points", path, rank))?;
+        let mut reader = BufReader::new(file);
+
+        let mut len_buf = [0u8; 8];
+        reader.read_exact(&mut len_buf)?;
+        let npoints = u64::from_le_bytes(len_buf) as usize;
+
+        let mut points = Vec::with_capacity(npoints);
+        for _ in 0..npoints {
+            let mut coordinate = [0 as PointType; 3];
+            for c in coordinate.iter_mut() {
+                let mut buf = [0u8; std::mem::size_of::<PointType>()];
+                reader.read_exact(&mut buf)?;
+                *c = PointType::from_le_bytes(buf);
+            }
+
+            let mut idx_buf = [0u8; 8];
+            reader.read_exact(&mut idx_buf)?;
+            let global_idx = u64::from_le_bytes(idx_buf) as usize;
+
+            let key = MortonKey::from_point(&coordinate, &domain);
+            points.push(Point {
+                coordinate,
+                global_idx,
+                key,
+            });
+        }
+
+        let points_to_keys =
+            DistributedTree::assign_nodes_to_leaves(&points.iter().map(|p| p.key).collect(), &tree.keys);
+
+        let seeds = DistributedTree::find_seeds(&tree.keys, &Morton);
+
+        Ok(DistributedTree {
+            balanced: true,
+            keys: tree.keys,
+            points,
+            points_to_keys,
+            domain,
+            curve: Box::new(Morton),
+            seeds,
+        })
+    }
+
+    /// Delta-encode `keys`' Morton ids as LEB128 varints, the same codec `Tree::save` uses for
+    /// its on-disk blocks, so `write_hdf5` can hand the result to a `CompressionType` before
+    /// writing it as a raw byte dataset.
+    fn encode_keys(keys: &[MortonKey]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        let mut previous = 0u64;
+        for key in keys {
+            let morton = key.morton();
+            write_varint(&mut payload, morton.wrapping_sub(previous)).unwrap();
+            previous = morton;
+        }
+        payload
+    }
+
+    /// Inverse of `encode_keys`.
+    fn decode_keys(payload: &[u8], nkeys: usize) -> Vec<MortonKey> {
+        let mut cursor = payload;
+        let mut keys = Vec::with_capacity(nkeys);
+        let mut previous = 0u64;
+        for _ in 0..nkeys {
+            let delta = read_varint(&mut cursor).unwrap();
+            let morton = previous.wrapping_add(delta);
+            keys.push(MortonKey::from_morton(morton));
+            previous = morton;
+        }
+        keys
+    }
+
+    /// Encode `points`' coordinates and global indices, the same fixed-width layout `save` uses
+    /// for its per-rank point file. `key` isn't stored since `decode_points` cheaply recomputes
+    /// it from `coordinate` and the tree's domain.
+    fn encode_points(points: &[Point]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for point in points {
+            for coordinate in point.coordinate {
+                payload.extend_from_slice(&coordinate.to_le_bytes());
+            }
+            payload.extend_from_slice(&(point.global_idx as u64).to_le_bytes());
+        }
+        payload
+    }
+
+    /// Inverse of `encode_points`.
+    fn decode_points(payload: &[u8], npoints: usize, domain: &Domain) -> io::Result<Vec<Point>> {
+        let mut reader = BufReader::new(payload);
+        let mut points = Vec::with_capacity(npoints);
+        for _ in 0..npoints {
+            let mut coordinate = [0 as PointType; 3];
+            for c in coordinate.iter_mut() {
+                let mut buf = [0u8; std::mem::size_of::<PointType>()];
+                reader.read_exact(&mut buf)?;
+                *c = PointType::from_le_bytes(buf);
+            }
+
+            let mut idx_buf = [0u8; 8];
+            reader.read_exact(&mut idx_buf)?;
+            let global_idx = u64::from_le_bytes(idx_buf) as usize;
+
+            let key = MortonKey::from_point(&coordinate, domain);
+            points.push(Point {
+                coordinate,
+                global_idx,
+                key,
+            });
+        }
+        Ok(points)
     }
 
     /// Read a DistributedTree from a from a HDF5 file on master process, and redistribute.
@@ -493,11 +953,6 @@ impl DistributedTree {
                 comm.abort(1);
             } else {
                 // Read global data into master process
-                let global_keys: Vec<MortonKey> = file
-                    .dataset("keys")
-                    .unwrap()
-                    .read_raw::<MortonKey>()
-                    .unwrap();
                 let global_key_counts: Vec<Count> = file
                     .dataset("key_counts")
                     .unwrap()
@@ -509,8 +964,6 @@ impl DistributedTree {
                     .read_raw::<Count>()
                     .unwrap();
 
-                let global_points: Vec<Point> =
-                    file.dataset("points").unwrap().read_raw::<Point>().unwrap();
                 let global_point_counts: Vec<Count> = file
                     .dataset("point_counts")
                     .unwrap()
@@ -541,6 +994,42 @@ impl DistributedTree {
                 global_domain = Domain { origin, diameter };
                 balanced = file.attr("balanced").unwrap().read_scalar().unwrap();
 
+                // Decompress and checksum-verify the `keys`/`points` blocks, then decode them
+                // back into typed buffers with `decode_keys`/`decode_points`.
+                let nkeys = global_key_counts.iter().sum::<Count>() as usize;
+                let keys_compression =
+                    CompressionType::from_tag(file.attr("keys_codec").unwrap().read_scalar().unwrap());
+                let keys_checksum: u64 = file.attr("keys_checksum").unwrap().read_scalar().unwrap();
+                let keys_raw_len: u64 = file.attr("keys_raw_len").unwrap().read_scalar().unwrap();
+                let keys_compressed: Vec<u8> =
+                    file.dataset("keys").unwrap().read_raw::<u8>().unwrap();
+                let keys_payload = keys_compression.decompress(&keys_compressed, keys_raw_len as usize);
+                assert_eq!(
+                    xxhash_rust::xxh3::xxh3_64(&keys_payload),
+                    keys_checksum,
+                    "checkpoint `keys` block failed its xxh3 checksum"
+                );
+                let global_keys = DistributedTree::decode_keys(&keys_payload, nkeys);
+
+                let npoints = global_point_counts.iter().sum::<Count>() as usize;
+                let points_compression = CompressionType::from_tag(
+                    file.attr("points_codec").unwrap().read_scalar().unwrap(),
+                );
+                let points_checksum: u64 =
+                    file.attr("points_checksum").unwrap().read_scalar().unwrap();
+                let points_raw_len: u64 = file.attr("points_raw_len").unwrap().read_scalar().unwrap();
+                let points_compressed: Vec<u8> =
+                    file.dataset("points").unwrap().read_raw::<u8>().unwrap();
+                let points_payload =
+                    points_compression.decompress(&points_compressed, points_raw_len as usize);
+                assert_eq!(
+                    xxhash_rust::xxh3::xxh3_64(&points_payload),
+                    points_checksum,
+                    "checkpoint `points` block failed its xxh3 checksum"
+                );
+                let global_points =
+                    DistributedTree::decode_points(&points_payload, npoints, &global_domain).unwrap();
+
                 // Distribute tree data to processes in communicator
                 let key_partition =
                     Partition::new(&global_keys[..], global_key_counts, &global_key_displs[..]);
@@ -571,20 +1060,31 @@ impl DistributedTree {
             &local_keys,
         );
 
+        let seeds = DistributedTree::find_seeds(&local_keys, &Morton);
+
         DistributedTree {
             keys: local_keys,
             points: local_points,
             points_to_keys: points_to_keys,
             balanced: balanced,
             domain: global_domain,
+            curve: Box::new(Morton),
+            seeds,
         }
     }
 
-    /// Serialize a DistributedTree to HDF5.
+    /// Serialize a DistributedTree to HDF5, compressing the gathered `keys`/`points` blocks with
+    /// `compression` before they hit disk.
+    ///
+    /// Morton keys sharing long common prefixes compress well once delta-encoded, so this uses
+    /// the same varint-delta codec `Tree::save` applies to its blocks rather than writing the
+    /// typed datasets `read_hdf5` used to expect; an xxh3 checksum of each uncompressed block is
+    /// stored alongside it so `read_hdf5` can detect silent corruption of the checkpoint file.
     pub fn write_hdf5(
         world: &UserCommunicator,
         filename: String,
         tree: &DistributedTree,
+        compression: CompressionType,
     ) -> hdf5::Result<()> {
         // Communicate global data to root process
         let comm = world.duplicate();
@@ -656,6 +1156,17 @@ impl DistributedTree {
             );
             root_process.gather_varcount_into_root(&local_points[..], &mut point_partition);
 
+            // Encode, compress and checksum the keys/points blocks
+            let keys_payload = DistributedTree::encode_keys(&global_keys);
+            let keys_checksum = xxhash_rust::xxh3::xxh3_64(&keys_payload);
+            let keys_raw_len = keys_payload.len() as u64;
+            let keys_compressed = compression.compress(&keys_payload);
+
+            let points_payload = DistributedTree::encode_points(&global_points);
+            let points_checksum = xxhash_rust::xxh3::xxh3_64(&points_payload);
+            let points_raw_len = points_payload.len() as u64;
+            let points_compressed = compression.compress(&points_payload);
+
             // Write data
             {
                 // Open file buffer
@@ -663,17 +1174,37 @@ impl DistributedTree {
 
                 // Write keys
                 let keys = file
-                    .new_dataset::<MortonKey>()
-                    .shape([global_keys.len()])
+                    .new_dataset::<u8>()
+                    .shape([keys_compressed.len()])
                     .create("keys")?;
-                keys.write(&global_keys)?;
+                keys.write_raw(&keys_compressed)?;
+
+                file.new_attr::<u32>()
+                    .create("keys_codec")?
+                    .write_scalar(&compression.tag())?;
+                file.new_attr::<u64>()
+                    .create("keys_checksum")?
+                    .write_scalar(&keys_checksum)?;
+                file.new_attr::<u64>()
+                    .create("keys_raw_len")?
+                    .write_scalar(&keys_raw_len)?;
 
                 // Write points
                 let points = file
-                    .new_dataset::<Point>()
-                    .shape([global_points.len()])
+                    .new_dataset::<u8>()
+                    .shape([points_compressed.len()])
                     .create("points")?;
-                points.write_raw(&global_points)?;
+                points.write_raw(&points_compressed)?;
+
+                file.new_attr::<u32>()
+                    .create("points_codec")?
+                    .write_scalar(&compression.tag())?;
+                file.new_attr::<u64>()
+                    .create("points_checksum")?
+                    .write_scalar(&points_checksum)?;
+                file.new_attr::<u64>()
+                    .create("points_raw_len")?
+                    .write_scalar(&points_raw_len)?;
 
                 // Write balance information as an attribute
                 let attr_builder = file.new_attr::<bool>();
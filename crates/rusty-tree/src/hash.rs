@@ -0,0 +1,329 @@
+//! Content-hash / Merkle-root subsystem for cross-rank tree verification.
+//!
+//! After a distributed balance pass, ranks need to confirm they agree on the global tree
+//! structure without exchanging the full key set. `NodeHasher` assigns a fixed `Node` digest to
+//! each leaf (from its Morton anchor/level, or a user payload via `leaf`), then `Tree::merkle_root`
+//! folds those up to `ROOT` the same way `Tree::aggregate` folds a `Summary`, except the combine
+//! step is 8-ary — one slot per child octant, `NodeHasher::empty()` standing in for any child
+//! absent from the tree — so two ranks' root hashes agree iff their completed key sets are
+//! identical, and can be compared directly or `Allreduce`'d by a benchmark harness.
+//!
+//! `DistributedTree::tree_hash` is a separate, binary scheme modeled on SSZ's cached tree hash:
+//! rather than folding the octree's own 8-ary structure, it Merkleizes a rank's sorted local leaf
+//! list pairwise (padded to a power of two), then Merkleizes the per-rank roots the same way. Use
+//! `merkle_root` to confirm a single balanced tree's key set is self-consistent across ranks;
+//! use `tree_hash` to fingerprint a whole distributed run for reproducibility checks.
+
+use std::collections::{HashMap, HashSet};
+
+use mpi::{topology::UserCommunicator, traits::*};
+
+use crate::{
+    constants::{DEEPEST_LEVEL, ROOT},
+    distributed::DistributedTree,
+    octree::Tree,
+    types::morton::MortonKey,
+};
+
+/// A fixed-size content hash, opaque to this crate (e.g. 32 bytes of blake3/xxh3 output).
+pub type Node = [u8; 32];
+
+/// Assigns and combines the per-node hashes that make up a `Tree::merkle_root`.
+pub trait NodeHasher {
+    /// The hash standing in for a child octant the tree has no node for.
+    fn empty() -> Node;
+
+    /// Combine `level` and its eight children's hashes (in child-Morton order, `empty()` for any
+    /// octant absent from the tree) into this node's own hash.
+    fn combine(level: u8, children: &[Node; 8]) -> Node;
+
+    /// The hash of a leaf at `key`, typically a hash of its anchor + level or a user payload.
+    fn leaf(key: &MortonKey) -> Node;
+}
+
+impl Tree {
+    /// Fold `H`'s per-leaf hashes bottom-up into a single root hash covering this tree's whole
+    /// key set: every interior node's hash is `H::combine` of its eight children's hashes
+    /// (`H::empty()` standing in for any child this tree has no node for), walked level by level
+    /// from the finest occupied level up to `ROOT` — the same grouping `aggregate` uses for a
+    /// `Summary`, but 8-ary instead of folding an arbitrary number of children.
+    pub fn merkle_root<H: NodeHasher>(&self) -> Node {
+        let mut hashes: HashMap<MortonKey, Node> =
+            self.keys.iter().map(|key| (*key, H::leaf(key))).collect();
+        let mut level_keys: Vec<MortonKey> = self.keys.clone();
+
+        for _ in (0..DEEPEST_LEVEL).rev() {
+            if level_keys.is_empty() {
+                break;
+            }
+
+            let mut by_parent: HashMap<MortonKey, Vec<MortonKey>> = HashMap::new();
+            for key in &level_keys {
+                if key.level() == 0 {
+                    continue;
+                }
+                by_parent.entry(key.parent()).or_insert_with(Vec::new).push(*key);
+            }
+
+            level_keys = by_parent.keys().cloned().collect();
+
+            for parent in level_keys.clone() {
+                let mut siblings: Vec<MortonKey> = parent.children().into_iter().collect();
+                siblings.sort();
+
+                let mut child_hashes = [H::empty(); 8];
+                for (slot, child) in siblings.iter().enumerate() {
+                    if let Some(hash) = hashes.get(child) {
+                        child_hashes[slot] = *hash;
+                    }
+                }
+
+                hashes.insert(parent, H::combine(parent.level() as u8, &child_hashes));
+            }
+        }
+
+        hashes.get(&ROOT).copied().unwrap_or_else(H::empty)
+    }
+}
+
+/// Combine `chunks` into a single root the way SSZ's "hash tree root" does: pad to the next
+/// power of two with zero chunks, then repeatedly blake3-hash adjacent pairs until one remains.
+/// Unlike `Tree::merkle_root`, this is agnostic to octree structure — it just folds a flat,
+/// already-ordered list of chunks — which is what lets `DistributedTree::tree_hash` reuse it both
+/// for a rank's own sorted leaf keys and for the small list of per-rank roots.
+fn merkleize(mut chunks: Vec<Node>) -> Node {
+    if chunks.is_empty() {
+        return [0u8; 32];
+    }
+
+    chunks.resize(chunks.len().next_power_of_two(), [0u8; 32]);
+
+    while chunks.len() > 1 {
+        chunks = chunks
+            .chunks(2)
+            .map(|pair| {
+                let mut hasher = blake3::Hasher::new();
+                hasher.update(&pair[0]);
+                hasher.update(&pair[1]);
+                *hasher.finalize().as_bytes()
+            })
+            .collect();
+    }
+
+    chunks[0]
+}
+
+/// Caches the leaf chunk hash `DistributedTree::tree_hash` assigns each of a rank's local keys,
+/// so a call after a small `insert_points`/`remove_points` only re-hashes the keys that actually
+/// changed rather than the whole local leaf list.
+#[derive(Debug, Default)]
+pub struct TreeHashCache {
+    chunks: HashMap<MortonKey, Node>,
+}
+
+impl TreeHashCache {
+    /// An empty cache; its first `tree_hash` call hashes every local key.
+    pub fn new() -> Self {
+        TreeHashCache::default()
+    }
+
+    fn chunk_for(&mut self, key: &MortonKey) -> Node {
+        *self
+            .chunks
+            .entry(*key)
+            .or_insert_with(|| *blake3::hash(&key.morton().to_le_bytes()).as_bytes())
+    }
+
+    /// Drop cached chunks for keys no longer present, so the cache doesn't grow unboundedly as
+    /// the tree is refined over many calls.
+    fn retain(&mut self, keys: &HashSet<MortonKey>) {
+        self.chunks.retain(|key, _| keys.contains(key));
+    }
+}
+
+impl DistributedTree {
+    /// A deterministic cryptographic digest of the whole distributed tree, following the
+    /// cached-tree-hash approach from SSZ: each rank Merkleizes its own sorted local leaf keys
+    /// into a root (via `cache`, so only dirtied keys are re-hashed), the roots are gathered in
+    /// rank order onto the root process, Merkleized again, and the result broadcast back to
+    /// every rank. Two runs — or a reloaded HDF5 checkpoint — with the same keys on each rank
+    /// produce the same fingerprint. The result depends on how the keys are divided among
+    /// ranks: the same global key set split differently (e.g. over a different number of
+    /// processes) generally gives a different fingerprint.
+    pub fn tree_hash(&self, world: &UserCommunicator, cache: &mut TreeHashCache) -> Node {
+        let local_keys: HashSet<MortonKey> = self.keys.iter().copied().collect();
+        cache.retain(&local_keys);
+
+        let mut sorted_keys = self.keys.clone();
+        sorted_keys.sort();
+
+        let chunks: Vec<Node> = sorted_keys.iter().map(|key| cache.chunk_for(key)).collect();
+        let local_root = merkleize(chunks);
+
+        let comm = world.duplicate();
+        let rank = comm.rank();
+        let size = comm.size();
+        let root_rank = 0;
+        let root_process = comm.process_at_rank(root_rank);
+
+        let mut global_root = [0u8; 32];
+
+        if rank == root_rank {
+            let mut all_roots = vec![[0u8; 32]; size as usize];
+            root_process.gather_into_root(&local_root, &mut all_roots[..]);
+            global_root = merkleize(all_roots);
+        } else {
+            root_process.gather_into(&local_root);
+        }
+
+        root_process.broadcast_into(&mut global_root);
+        global_root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+    use rand::SeedableRng;
+
+    use crate::types::{domain::Domain, point::Point};
+
+    /// A `NodeHasher` built from plain `blake3`, with no cryptographic pretensions beyond being
+    /// deterministic and sensitive to both level and child order — enough to exercise
+    /// `Tree::merkle_root`'s folding without pulling in a real digest scheme.
+    struct TestHasher;
+
+    impl NodeHasher for TestHasher {
+        fn empty() -> Node {
+            [0u8; 32]
+        }
+
+        fn combine(level: u8, children: &[Node; 8]) -> Node {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(&[level]);
+            for child in children {
+                hasher.update(child);
+            }
+            *hasher.finalize().as_bytes()
+        }
+
+        fn leaf(key: &MortonKey) -> Node {
+            *blake3::hash(&key.morton().to_le_bytes()).as_bytes()
+        }
+    }
+
+    fn keys_fixture(seed: u64) -> Vec<MortonKey> {
+        let npoints: u64 = 200;
+        let domain = Domain {
+            origin: [0., 0., 0.],
+            diameter: [1., 1., 1.],
+        };
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let between = rand::distributions::Uniform::from(0.0..1.0);
+
+        (0..npoints)
+            .map(|_| {
+                let coordinate = [
+                    between.sample(&mut rng),
+                    between.sample(&mut rng),
+                    between.sample(&mut rng),
+                ];
+                let point = Point {
+                    coordinate,
+                    global_idx: 0,
+                    key: MortonKey::from_point(&coordinate, &domain),
+                };
+                point.key
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merkle_root_is_order_independent() {
+        let keys = keys_fixture(0);
+
+        let mut shuffled = keys.clone();
+        shuffled.shuffle(&mut StdRng::seed_from_u64(1));
+
+        let forward = Tree { keys };
+        let shuffled = Tree { keys: shuffled };
+
+        assert_eq!(
+            forward.merkle_root::<TestHasher>(),
+            shuffled.merkle_root::<TestHasher>()
+        );
+    }
+
+    #[test]
+    fn test_merkle_root_changes_with_key_set() {
+        let keys = keys_fixture(0);
+
+        let mut with_extra = keys.clone();
+        with_extra.push(with_extra[0].children()[0]);
+
+        let original = Tree { keys };
+        let extra = Tree { keys: with_extra };
+
+        assert_ne!(
+            original.merkle_root::<TestHasher>(),
+            extra.merkle_root::<TestHasher>()
+        );
+    }
+
+    #[test]
+    fn test_merkle_root_empty_tree_is_empty_hash() {
+        let tree = Tree { keys: Vec::new() };
+        assert_eq!(tree.merkle_root::<TestHasher>(), TestHasher::empty());
+    }
+
+    fn chunk(byte: u8) -> Node {
+        [byte; 32]
+    }
+
+    fn blake3_pair(a: Node, b: Node) -> Node {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&a);
+        hasher.update(&b);
+        *hasher.finalize().as_bytes()
+    }
+
+    #[test]
+    fn test_merkleize_empty_is_zero_chunk() {
+        assert_eq!(merkleize(Vec::new()), [0u8; 32]);
+    }
+
+    #[test]
+    fn test_merkleize_single_chunk_is_itself() {
+        let a = chunk(1);
+        assert_eq!(merkleize(vec![a]), a);
+    }
+
+    #[test]
+    fn test_merkleize_two_chunks_matches_manual_pair() {
+        let a = chunk(1);
+        let b = chunk(2);
+        assert_eq!(merkleize(vec![a, b]), blake3_pair(a, b));
+    }
+
+    #[test]
+    fn test_merkleize_pads_odd_length_with_zero_chunks() {
+        let a = chunk(1);
+        let b = chunk(2);
+        let c = chunk(3);
+        let zero = [0u8; 32];
+
+        // 3 chunks pad to 4, then fold pairwise: (a, b) and (c, zero).
+        let expected = blake3_pair(blake3_pair(a, b), blake3_pair(c, zero));
+        assert_eq!(merkleize(vec![a, b, c]), expected);
+    }
+
+    #[test]
+    fn test_merkleize_is_order_sensitive() {
+        let a = chunk(1);
+        let b = chunk(2);
+        assert_ne!(merkleize(vec![a, b]), merkleize(vec![b, a]));
+    }
+}
@@ -1,8 +1,10 @@
 //! Assorted helper functions.
 
+use mpi::{collective::SystemOperation, topology::UserCommunicator, traits::*};
+
 use crate::{
     types::{
-        point::Point,
+        point::{Point, PointType},
         domain::Domain
     },
 };
@@ -11,22 +13,55 @@ use crate::{
 /// Compute the points bounds for points on a local node
 pub fn compute_bounds(points: &Vec<Point>) {
 
-    let max_x = points.iter().map(|p| p.coordinate[0]); 
-    // let max_y = points.iter().map(|p| y.coordinate[1]).collect().max().unwrap(); 
-    // let max_z = points.iter().map(|p| z.coordinate[2]).collect().max().unwrap(); 
-    
-    // let min_x = points.iter().map(|p| p.coordinate[0]).collect().min().unwrap(); 
-    // let min_y = points.iter().map(|p| y.coordinate[1]).collect().min().unwrap(); 
-    // let min_z = points.iter().map(|p| z.coordinate[2]).collect().min().unwrap(); 
+    let max_x = points.iter().map(|p| p.coordinate[0]);
+    // let max_y = points.iter().map(|p| y.coordinate[1]).collect().max().unwrap();
+    // let max_z = points.iter().map(|p| z.coordinate[2]).collect().max().unwrap();
+
+    // let min_x = points.iter().map(|p| p.coordinate[0]).collect().min().unwrap();
+    // let min_y = points.iter().map(|p| y.coordinate[1]).collect().min().unwrap();
+    // let min_z = points.iter().map(|p| z.coordinate[2]).collect().min().unwrap();
 
     println!("max {:?}", max_x);
 }
 
 
 
-/// Compute the points bounds over all nodes.
-pub fn compute_bounds_global() {
+/// Compute a consistent bounding box for points distributed across every rank in `world`.
+///
+/// Each rank folds its own `points` down to a local per-axis min/max, then `Allreduce`s those
+/// six scalars with `MPI_MIN`/`MPI_MAX` so every rank settles on the same `origin`/`diameter`.
+/// Without this shared frame, two ranks could otherwise Morton-encode their points against
+/// different bounding boxes, silently corrupting the distributed ordering `DistributedTree::new`
+/// relies on.
+pub fn compute_bounds_global(points: &[[PointType; 3]], world: &UserCommunicator) -> Domain {
+    const TOL: PointType = 1E-5;
+
+    let mut local_min = [PointType::INFINITY; 3];
+    let mut local_max = [PointType::NEG_INFINITY; 3];
+
+    for point in points {
+        for dim in 0..3 {
+            local_min[dim] = local_min[dim].min(point[dim]);
+            local_max[dim] = local_max[dim].max(point[dim]);
+        }
+    }
+
+    let mut global_min = [0 as PointType; 3];
+    let mut global_max = [0 as PointType; 3];
+
+    for dim in 0..3 {
+        world.all_reduce_into(&local_min[dim], &mut global_min[dim], SystemOperation::min());
+        world.all_reduce_into(&local_max[dim], &mut global_max[dim], SystemOperation::max());
+    }
+
+    let mut origin = [0 as PointType; 3];
+    let mut diameter = [0 as PointType; 3];
+    for dim in 0..3 {
+        origin[dim] = global_min[dim];
+        diameter[dim] = (global_max[dim] - global_min[dim]) * (1.0 + TOL);
+    }
 
+    Domain { origin, diameter }
 }
 
 
@@ -36,8 +71,27 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_compute_bounds() {
-        assert!(false);
+    fn test_compute_bounds_global_matches_local_bounds_on_one_rank() {
+        // `compute_bounds_global` just needs a `UserCommunicator` to `Allreduce` across, which
+        // `duplicate()` gives us even on a single-process `mpi::initialize()` world; with one
+        // rank the "global" reduction is exactly the local min/max, so this still exercises the
+        // real per-axis min/max + tolerance logic rather than a single-point degenerate case.
+        let universe = mpi::initialize().expect("MPI is already initialized on this process");
+        let world = universe.world().duplicate();
+
+        let points: [[PointType; 3]; 3] = [[0.2, 5.0, -1.0], [-3.0, 2.0, 4.0], [1.5, -2.5, 0.0]];
+
+        let domain = compute_bounds_global(&points, &world);
+
+        let expected_min = [-3.0, -2.5, -1.0];
+        let expected_max = [1.5, 5.0, 4.0];
+
+        for dim in 0..3 {
+            assert!((domain.origin[dim] - expected_min[dim]).abs() < 1e-9);
+            // `diameter` is widened by a relative tolerance, so the domain's far edge lies
+            // strictly past the largest coordinate on each axis.
+            assert!(domain.origin[dim] + domain.diameter[dim] > expected_max[dim]);
+        }
     }
 }
 
@@ -0,0 +1,221 @@
+//! Weighted rank layout and rebalancing, tracked independently of the key-range partition
+//! `hyksort` produces.
+//!
+//! `unbalanced_tree`/`balanced_tree` split keys into equal-*count* ranges, which can leave ranks
+//! holding very different numbers of *points* once particle density varies across the domain.
+//! `TreeLayout` records, per rank, the Morton key each rank's interval starts at and the point
+//! weight that interval carried when the layout was last recorded; `rebalance` recomputes those
+//! boundaries so each rank's weight sits within `target_imbalance` of the mean, migrating only
+//! the points that cross a shifted boundary. Because a `DistributedTree`'s ranks already hold
+//! contiguous, increasing key ranges, migration only ever needs to happen between a rank and its
+//! immediate neighbors — the same adjacency `balance_distributed` ripples ghosts across.
+
+use mpi::{
+    topology::{Rank, UserCommunicator},
+    traits::*,
+    Count,
+};
+
+use crate::{distributed::DistributedTree, types::morton::MortonKey, types::point::Point};
+
+/// The key a single rank's contiguous interval starts at, and the point weight it carried when
+/// this was recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RankAssignment {
+    pub rank: Rank,
+    pub start: MortonKey,
+    pub weight: usize,
+}
+
+/// A versioned snapshot of every rank's key-interval assignment. `version` increases by one each
+/// time `rebalance` produces a new layout; `previous` retains the layout it moved away from, so a
+/// caller can tell exactly which ranks' intervals (and therefore which points) actually moved.
+#[derive(Debug, Clone)]
+pub struct TreeLayout {
+    pub version: u64,
+    pub assignments: Vec<RankAssignment>,
+    pub previous: Option<Box<TreeLayout>>,
+}
+
+impl TreeLayout {
+    /// The layout `tree` currently has: each rank's interval starts at its minimum local key and
+    /// carries however many points it currently holds. `version` starts at 0 and has no
+    /// `previous`, since this reflects whatever partition `hyksort` produced rather than the
+    /// result of a `rebalance` call.
+    pub fn from_tree(world: &UserCommunicator, tree: &DistributedTree) -> TreeLayout {
+        let comm = world.duplicate();
+        let size = comm.size();
+
+        let local_start = *tree.keys.iter().min().unwrap();
+        let local_weight = tree.points.len() as Count;
+
+        let mut starts = vec![MortonKey::default(); size as usize];
+        let mut weights = vec![0 as Count; size as usize];
+        comm.all_gather_into(&local_start, &mut starts[..]);
+        comm.all_gather_into(&local_weight, &mut weights[..]);
+
+        let assignments = starts
+            .into_iter()
+            .zip(weights)
+            .enumerate()
+            .map(|(rank, (start, weight))| RankAssignment {
+                rank: rank as Rank,
+                start,
+                weight: weight as usize,
+            })
+            .collect();
+
+        TreeLayout {
+            version: 0,
+            assignments,
+            previous: None,
+        }
+    }
+
+    /// The fraction by which `rank`'s weight departs from the mean weight across all ranks.
+    fn imbalance(&self, rank: Rank) -> f64 {
+        let total: usize = self.assignments.iter().map(|a| a.weight).sum();
+        let mean = total as f64 / self.assignments.len() as f64;
+        if mean == 0.0 {
+            return 0.0;
+        }
+        let weight = self.assignments[rank as usize].weight as f64;
+        (weight - mean).abs() / mean
+    }
+
+    /// Whether every rank's weight is within `target_imbalance` of the mean.
+    fn is_balanced(&self, target_imbalance: f64) -> bool {
+        (0..self.assignments.len() as Rank).all(|rank| self.imbalance(rank) <= target_imbalance)
+    }
+}
+
+impl DistributedTree {
+    /// Shift points across rank boundaries until every rank's point count is within
+    /// `target_imbalance` of the mean (or `max_rounds` adjacent-exchange rounds have run),
+    /// returning the resulting `TreeLayout`.
+    ///
+    /// Each round, a rank compares its point count against its ideal share and trades excess
+    /// points with `previous`/`next` — the same pair of neighbors `balance_distributed` ripples
+    /// ghost octants across — moving the rank boundary towards the mean one hop at a time. This
+    /// converges over several rounds for imbalances spanning more than one neighbor, the same way
+    /// `balance_distributed` loops its ghost exchange until nothing new gets inserted.
+    pub fn rebalance(
+        &mut self,
+        world: &UserCommunicator,
+        target_imbalance: f64,
+    ) -> TreeLayout {
+        let comm = world.duplicate();
+        let rank = comm.rank();
+        let size = comm.size();
+
+        let mut layout = TreeLayout::from_tree(&comm, self);
+
+        if size == 1 {
+            return layout;
+        }
+
+        let max_rounds = size as usize;
+
+        for _ in 0..max_rounds {
+            if layout.is_balanced(target_imbalance) {
+                break;
+            }
+
+            let total: usize = layout.assignments.iter().map(|a| a.weight).sum();
+            let mean = (total as f64 / size as f64).round() as usize;
+
+            let previous_rank = if rank > 0 { rank - 1 } else { size - 1 };
+            let next_rank = if rank + 1 < size { rank + 1 } else { 0 };
+
+            self.points.sort();
+
+            let local_weight = self.points.len();
+
+            // If we're overfull, hand our lowest-keyed excess to `previous` (rank 0 hands its
+            // highest-keyed excess to `next` instead), whatever the neighbor's own weight; a single
+            // round only ever trades with immediate neighbors.
+            let give_previous = if rank > 0 && local_weight > mean {
+                (local_weight - mean).min(self.points.len())
+            } else {
+                0
+            };
+
+            let mut outgoing_previous = Vec::new();
+            if give_previous > 0 {
+                outgoing_previous = self.points.drain(0..give_previous).collect();
+            }
+
+            let give_next = if rank + 1 < size && self.points.len() > mean {
+                self.points.len() - mean
+            } else {
+                0
+            };
+
+            let mut outgoing_next = Vec::new();
+            if give_next > 0 {
+                let split_at = self.points.len() - give_next;
+                outgoing_next = self.points.split_off(split_at);
+            }
+
+            let previous_process = comm.process_at_rank(previous_rank);
+            let next_process = comm.process_at_rank(next_rank);
+
+            if rank > 0 {
+                let nsend = outgoing_previous.len() as Count;
+                previous_process.send(&nsend);
+                previous_process.send(&outgoing_previous[..]);
+            }
+            if rank + 1 < size {
+                let nsend = outgoing_next.len() as Count;
+                next_process.send(&nsend);
+                next_process.send(&outgoing_next[..]);
+            }
+
+            if rank + 1 < size {
+                let mut nrecv = 0 as Count;
+                next_process.receive_into(&mut nrecv);
+                let mut incoming = vec![Point::default(); nrecv as usize];
+                next_process.receive_into(&mut incoming[..]);
+                self.points.extend(incoming);
+            }
+            if rank > 0 {
+                let mut nrecv = 0 as Count;
+                previous_process.receive_into(&mut nrecv);
+                let mut incoming = vec![Point::default(); nrecv as usize];
+                previous_process.receive_into(&mut incoming[..]);
+                self.points.extend(incoming);
+            }
+
+            self.points.sort();
+            self.keys = self.points.iter().map(|p| p.key).collect();
+            self.keys.sort();
+            self.keys.dedup();
+            // Every local key is still a leaf in its own right after migrating raw points (no
+            // coarser blocktree is maintained here), so each maps to itself.
+            self.points_to_keys = self.keys.iter().map(|key| (*key, *key)).collect();
+            self.seeds = DistributedTree::find_seeds(&self.keys, self.curve.as_ref());
+
+            let next_layout = TreeLayout::from_tree(&comm, self);
+
+            let moved: usize = (0..size)
+                .map(|r| {
+                    let before = layout.assignments[r as usize].weight;
+                    let after = next_layout.assignments[r as usize].weight;
+                    before.abs_diff(after)
+                })
+                .sum();
+
+            layout = TreeLayout {
+                version: layout.version + 1,
+                assignments: next_layout.assignments,
+                previous: Some(Box::new(layout)),
+            };
+
+            if moved == 0 {
+                break;
+            }
+        }
+
+        layout
+    }
+}
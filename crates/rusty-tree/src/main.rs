@@ -48,7 +48,7 @@ fn main() {
     let balanced = balanced_tree_fixture(&world);
 
     let comm = world.duplicate();
-    // DistributedTree::write_hdf5(&comm, "foo".to_string(), &balanced);
+    // DistributedTree::write_hdf5(&comm, "foo".to_string(), &balanced, CompressionType::Lz4);
 
     let tree = DistributedTree::read_hdf5(&comm, "foo.hdf5".to_string());
 
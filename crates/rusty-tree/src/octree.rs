@@ -1,17 +1,191 @@
 // //! Data structures and functions to create regular and adaptive Octrees.
 
 use std::{
+    cmp::{Ordering, Reverse},
     ops::{Deref, DerefMut},
-    collections::{HashSet}
+    collections::{BinaryHeap, HashMap, HashSet},
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
 };
 
 use itertools::Itertools;
 
 use crate::{
-    constants::DEEPEST_LEVEL,
-    types::morton::{MortonKey}
+    augmented::Summary,
+    constants::{DEEPEST_LEVEL, ROOT},
+    curve::SpaceFillingCurve,
+    types::{
+        domain::Domain,
+        morton::MortonKey,
+        point::{Point, PointType},
+    },
 };
 
+/// Magic bytes identifying a serialized linear tree file, used by `Tree::save`/`Tree::load`.
+const TREE_MAGIC: [u8; 4] = *b"RTRE";
+
+/// Number of keys grouped into a single LEB128-encoded, optionally compressed block.
+const TREE_BLOCK_SIZE: usize = 1024;
+
+/// Compression applied to each block written by `Tree::save`, and to the dataset buffers
+/// `distributed::DistributedTree::write_hdf5` gathers onto the root rank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionType {
+    /// Store the encoded bytes as-is.
+    None,
+    /// LZ4-compress the encoded bytes.
+    Lz4,
+    /// Deflate-compress the encoded bytes at the given level (0-9, see `flate2::Compression`).
+    Deflate(u32),
+}
+
+impl CompressionType {
+    /// The `u32` tag `save`/`write_hdf5` persist alongside the compressed payload so `load`/
+    /// `read_hdf5` know which codec (and, for `Deflate`, which level) produced it.
+    pub(crate) fn tag(&self) -> u32 {
+        match self {
+            CompressionType::None => 0,
+            CompressionType::Lz4 => 1,
+            CompressionType::Deflate(level) => 0x8000_0000 | level,
+        }
+    }
+
+    /// Reconstruct a `CompressionType` from a tag written by `tag`.
+    pub(crate) fn from_tag(tag: u32) -> Self {
+        if tag & 0x8000_0000 != 0 {
+            CompressionType::Deflate(tag & 0x7fff_ffff)
+        } else {
+            match tag {
+                0 => CompressionType::None,
+                1 => CompressionType::Lz4,
+                other => panic!("unknown compression tag {}", other),
+            }
+        }
+    }
+
+    /// Compress `payload` with this codec.
+    pub fn compress(&self, payload: &[u8]) -> Vec<u8> {
+        match self {
+            CompressionType::None => payload.to_vec(),
+            CompressionType::Lz4 => lz4_flex::compress(payload),
+            CompressionType::Deflate(level) => {
+                use flate2::{write::DeflateEncoder, Compression};
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::new(*level));
+                encoder.write_all(payload).unwrap();
+                encoder.finish().unwrap()
+            }
+        }
+    }
+
+    /// Decompress `payload`, previously produced by `compress` with this same codec.
+    /// `size_hint` is an upper bound on the decompressed length, only consulted by `Lz4`.
+    pub fn decompress(&self, payload: &[u8], size_hint: usize) -> Vec<u8> {
+        match self {
+            CompressionType::None => payload.to_vec(),
+            CompressionType::Lz4 => lz4_flex::decompress(payload, size_hint),
+            CompressionType::Deflate(_) => {
+                use flate2::read::DeflateDecoder;
+                let mut decoder = DeflateDecoder::new(payload);
+                let mut out = Vec::new();
+                decoder.read_to_end(&mut out).unwrap();
+                out
+            }
+        }
+    }
+}
+
+/// Write `value` as an unsigned LEB128 varint.
+///
+/// `pub(crate)` so `distributed::DistributedTree::write_hdf5`/`read_hdf5` can delta-encode their
+/// gathered key buffer the same way `Tree::save` does.
+pub(crate) fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        writer.write_all(&[byte])?;
+        if value == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Read an unsigned LEB128 varint.
+pub(crate) fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(value)
+}
+
+
+/// Squared Euclidean distance from `point` to its closest point on `key`'s bounding box, 0 if
+/// `point` lies inside the box. Same box-distance heuristic `query`'s `DistributedTree::knn`
+/// branch-and-bound uses, duplicated here since a single-node `Tree` has no points/domain of its
+/// own to route the query through.
+fn box_distance_squared(key: &MortonKey, point: &[PointType; 3], domain: &Domain) -> PointType {
+    let corners = key.box_coordinates(domain);
+
+    let mut min = [PointType::INFINITY; 3];
+    let mut max = [PointType::NEG_INFINITY; 3];
+    for corner in corners.chunks(3) {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(corner[axis]);
+            max[axis] = max[axis].max(corner[axis]);
+        }
+    }
+
+    let mut distance_squared = 0.0;
+    for axis in 0..3 {
+        let d = if point[axis] < min[axis] {
+            min[axis] - point[axis]
+        } else if point[axis] > max[axis] {
+            point[axis] - max[axis]
+        } else {
+            0.0
+        };
+        distance_squared += d * d;
+    }
+    distance_squared
+}
+
+/// Squared Euclidean distance between two points.
+fn point_distance_squared(a: &[PointType; 3], b: &[PointType; 3]) -> PointType {
+    (0..3).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+}
+
+/// Wraps a heap payload with an `f64` priority, ordering purely on that priority — `f64` isn't
+/// `Ord`, but every distance fed into `Tree::knn`/`Tree::points_within_radius` is finite.
+struct ByDistance<T>(PointType, T);
+
+impl<T> PartialEq for ByDistance<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl<T> Eq for ByDistance<T> {}
+impl<T> PartialOrd for ByDistance<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.0.partial_cmp(&other.0)
+    }
+}
+impl<T> Ord for ByDistance<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap()
+    }
+}
 
 #[derive(Debug)]
 pub struct Tree {
@@ -72,6 +246,58 @@ impl Tree {
         minimal_tree
     }
 
+    /// Parallel, level-synchronous counterpart to `complete_region`, behind the `rayon` feature:
+    /// rather than draining a single LIFO work list, each level below the finest common ancestor
+    /// is expanded as a frontier, tested and subdivided with `par_iter`/fold/reduce, and the
+    /// accepted nodes and next frontier are combined before moving one level down. Kept alongside
+    /// `complete_region` for correctness comparison.
+    #[cfg(feature = "rayon")]
+    pub fn complete_region_parallel(a: &MortonKey, b: &MortonKey) -> Vec<MortonKey> {
+        use rayon::prelude::*;
+
+        let mut a_ancestors: HashSet<MortonKey> = a.ancestors();
+        let mut b_ancestors: HashSet<MortonKey> = b.ancestors();
+
+        a_ancestors.remove(a);
+        b_ancestors.remove(b);
+
+        let mut frontier: Vec<MortonKey> = a.finest_ancestor(&b).children().into_iter().collect();
+
+        let mut minimal_tree: Vec<MortonKey> = Vec::new();
+
+        while !frontier.is_empty() {
+            let (accepted, expanded): (Vec<MortonKey>, Vec<Vec<MortonKey>>) = frontier
+                .par_iter()
+                .fold(
+                    || (Vec::new(), Vec::new()),
+                    |mut acc: (Vec<MortonKey>, Vec<Vec<MortonKey>>), current_item| {
+                        if (*current_item > *a) & (*current_item < *b) & !b_ancestors.contains(current_item)
+                        {
+                            acc.0.push(*current_item);
+                        } else if (a_ancestors.contains(current_item)) | (b_ancestors.contains(current_item))
+                        {
+                            acc.1.push(current_item.children());
+                        }
+                        acc
+                    },
+                )
+                .reduce(
+                    || (Vec::new(), Vec::new()),
+                    |mut a, mut b| {
+                        a.0.append(&mut b.0);
+                        a.1.append(&mut b.1);
+                        a
+                    },
+                );
+
+            minimal_tree.extend(accepted);
+            frontier = expanded.into_iter().flatten().collect();
+        }
+
+        minimal_tree.sort();
+        minimal_tree
+    }
+
     pub fn complete(self: &mut Tree) {
         let a = self.keys.iter().min().unwrap();
         let b = self.keys.iter().max().unwrap();
@@ -91,6 +317,279 @@ impl Tree {
         self.keys.sort();
     }
 
+    /// Locate the unique leaf containing `key`, in O(log n): binary search the sorted key array,
+    /// falling back to checking whether the key immediately before the insertion point is an
+    /// ancestor of `key` (the case where `key` is finer than any leaf, e.g. a point's encoded
+    /// key rather than a leaf itself).
+    pub fn find_leaf(&self, key: &MortonKey) -> Option<usize> {
+        match self.keys.binary_search(key) {
+            Ok(idx) => Some(idx),
+            Err(idx) => {
+                if idx > 0 && self.keys[idx - 1].is_ancestor(key) {
+                    Some(idx - 1)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// The `k` points in `points` closest to `query`, found by a best-first branch-and-bound
+    /// descent of this tree's boxes rather than a flat scan of `points`.
+    ///
+    /// A min-heap of visited `MortonKey`s, ordered by squared box-to-`query` distance
+    /// (`box_distance_squared`, via `MortonKey::box_coordinates`), is popped in increasing
+    /// distance order. A leaf key's matching points are tested against a max-heap of the best
+    /// `k` candidates found so far (the same bounded-heap pattern `CoverTree::knn` uses); an
+    /// interior key is expanded into whichever children actually lead to a leaf of this tree. A
+    /// popped node whose box distance already exceeds the current k-th best distance is dropped
+    /// without expanding, since none of its descendants can be closer than its own box.
+    pub fn knn(&self, points: &[Point], domain: &Domain, query: [PointType; 3], k: usize) -> Vec<Point> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let leaves: HashSet<MortonKey> = self.keys.iter().cloned().collect();
+
+        let mut frontier = BinaryHeap::new();
+        frontier.push(Reverse(ByDistance(box_distance_squared(&ROOT, &query, domain), ROOT)));
+
+        let mut best = BinaryHeap::<ByDistance<Point>>::new();
+
+        while let Some(Reverse(ByDistance(node_distance, key))) = frontier.pop() {
+            if best.len() >= k && node_distance > best.peek().unwrap().0 {
+                continue;
+            }
+
+            if leaves.contains(&key) {
+                for &point in points.iter().filter(|point| point.key == key) {
+                    let distance = point_distance_squared(&point.coordinate, &query);
+                    if best.len() < k {
+                        best.push(ByDistance(distance, point));
+                    } else if distance < best.peek().unwrap().0 {
+                        best.pop();
+                        best.push(ByDistance(distance, point));
+                    }
+                }
+                continue;
+            }
+
+            for child in key.children() {
+                if leaves.iter().any(|leaf| child == *leaf || child.is_ancestor(leaf)) {
+                    let distance = box_distance_squared(&child, &query, domain);
+                    frontier.push(Reverse(ByDistance(distance, child)));
+                }
+            }
+        }
+
+        let mut result: Vec<(PointType, Point)> =
+            best.into_iter().map(|ByDistance(d, p)| (d, p)).collect();
+        result.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        result.into_iter().map(|(_, p)| p).collect()
+    }
+
+    /// Every point in `points` within `radius` of `query`, via the same best-first descent as
+    /// `knn`, except the fixed `radius` (squared) is the pruning threshold in place of a
+    /// shrinking k-th-best bound, and every match within it is kept rather than just the `k`
+    /// closest.
+    pub fn points_within_radius(
+        &self,
+        points: &[Point],
+        domain: &Domain,
+        query: [PointType; 3],
+        radius: PointType,
+    ) -> Vec<Point> {
+        let radius_squared = radius * radius;
+        let leaves: HashSet<MortonKey> = self.keys.iter().cloned().collect();
+
+        let mut frontier = BinaryHeap::new();
+        frontier.push(Reverse(ByDistance(box_distance_squared(&ROOT, &query, domain), ROOT)));
+
+        let mut matches: Vec<(PointType, Point)> = Vec::new();
+
+        while let Some(Reverse(ByDistance(node_distance, key))) = frontier.pop() {
+            if node_distance > radius_squared {
+                continue;
+            }
+
+            if leaves.contains(&key) {
+                matches.extend(points.iter().filter_map(|point| {
+                    if point.key != key {
+                        return None;
+                    }
+                    let distance = point_distance_squared(&point.coordinate, &query);
+                    (distance <= radius_squared).then(|| (distance, *point))
+                }));
+                continue;
+            }
+
+            for child in key.children() {
+                if leaves.iter().any(|leaf| child == *leaf || child.is_ancestor(leaf)) {
+                    let distance = box_distance_squared(&child, &query, domain);
+                    frontier.push(Reverse(ByDistance(distance, child)));
+                }
+            }
+        }
+
+        matches.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        matches.into_iter().map(|(_, p)| p).collect()
+    }
+
+    /// Insert `key` into this tree, keeping `keys` sorted, linear (ancestor-free) and — if the
+    /// tree was complete before the call — complete again. Rather than re-running
+    /// `linearize`/`complete` over the whole key set, only the local neighborhood is touched:
+    /// binary-search `key`'s sorted position, drop any existing ancestor/descendant of `key`
+    /// within that window (a node's descendants always occupy a contiguous run in sorted order,
+    /// so the conflicting window never extends past the first non-conflicting neighbor on either
+    /// side), then re-run `complete_region` between `key` and the neighbor now bracketing it on
+    /// each side to fill whatever gap the insertion opened up.
+    pub fn insert(&mut self, key: MortonKey) {
+        let idx = match self.keys.binary_search(&key) {
+            Ok(_) => return,
+            Err(idx) => idx,
+        };
+
+        let mut start = idx;
+        while start > 0
+            && (self.keys[start - 1].is_ancestor(&key) || key.is_ancestor(&self.keys[start - 1]))
+        {
+            start -= 1;
+        }
+
+        let mut end = idx;
+        while end < self.keys.len()
+            && (self.keys[end].is_ancestor(&key) || key.is_ancestor(&self.keys[end]))
+        {
+            end += 1;
+        }
+
+        let mut replacement = vec![key];
+
+        if start > 0 {
+            let left = self.keys[start - 1];
+            let mut gap = Tree::complete_region(&left, &key);
+            replacement.append(&mut gap);
+        }
+        if end < self.keys.len() {
+            let right = self.keys[end];
+            let mut gap = Tree::complete_region(&key, &right);
+            replacement.append(&mut gap);
+        }
+
+        replacement.sort();
+        self.keys.splice(start..end, replacement);
+    }
+
+    /// Remove `key` from this tree, if present, keeping `keys` sorted and — if the tree was
+    /// complete before the call — complete again: rather than re-running `complete` over the
+    /// whole key set, only re-run `complete_region` between the two neighbors the removal leaves
+    /// bracketing the resulting gap.
+    pub fn remove(&mut self, key: &MortonKey) {
+        let idx = match self.keys.binary_search(key) {
+            Ok(idx) => idx,
+            Err(_) => return,
+        };
+
+        self.keys.remove(idx);
+
+        if idx > 0 && idx < self.keys.len() {
+            let left = self.keys[idx - 1];
+            let right = self.keys[idx];
+            let gap = Tree::complete_region(&left, &right);
+            self.keys.splice(idx..idx, gap);
+        }
+    }
+
+    /// Sort this tree's keys by `curve`'s ordering (e.g. `Hilbert`) instead of `MortonKey`'s
+    /// natural Z-order `Ord`.
+    pub fn sort_by_curve(self: &mut Tree, curve: &dyn SpaceFillingCurve) {
+        self.keys.sort_by_key(|key| curve.sort_key(key));
+    }
+
+    /// Like `linearize`, but leaves the keys in `curve`'s order. `linearize_keys` only drops a
+    /// key that is an ancestor of the key right after it, which holds for every ancestor in
+    /// Morton order but not in `curve`'s, so ancestors are removed in Morton order first and the
+    /// result is then sorted by `curve`.
+    pub fn linearize_by_curve(self: &mut Tree, curve: &dyn SpaceFillingCurve) {
+        self.keys.sort();
+        self.keys = Tree::linearize_keys(self.keys.clone());
+        self.sort_by_curve(curve);
+    }
+
+    /// Serialize this tree's sorted keys to `path` as delta-encoded LEB128 varints, grouped
+    /// into fixed-size blocks and each block compressed with `compression`.
+    ///
+    /// Keys are assumed to already be sorted (as they are after `linearize`/`complete`), so
+    /// each block stores the deltas between consecutive Morton ids, which are small and
+    /// compress well for trees with shared high bits.
+    pub fn save<P: AsRef<Path>>(&self, path: P, compression: CompressionType) -> io::Result<()> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+
+        writer.write_all(&TREE_MAGIC)?;
+        writer.write_all(&compression.tag().to_le_bytes())?;
+        writer.write_all(&(self.keys.len() as u64).to_le_bytes())?;
+
+        for block in self.keys.chunks(TREE_BLOCK_SIZE) {
+            let mut payload = Vec::new();
+            let mut previous = 0u64;
+            for key in block {
+                let morton = key.morton();
+                write_varint(&mut payload, morton.wrapping_sub(previous))?;
+                previous = morton;
+            }
+
+            let payload = compression.compress(&payload);
+
+            writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+            writer.write_all(&payload)?;
+        }
+
+        Ok(())
+    }
+
+    /// Load a tree previously written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Tree> {
+        let file = File::open(path)?;
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        assert_eq!(magic, TREE_MAGIC, "not a linear tree file");
+
+        let mut compression_buf = [0u8; 4];
+        reader.read_exact(&mut compression_buf)?;
+        let compression = CompressionType::from_tag(u32::from_le_bytes(compression_buf));
+
+        let mut len_buf = [0u8; 8];
+        reader.read_exact(&mut len_buf)?;
+        let nkeys = u64::from_le_bytes(len_buf) as usize;
+
+        let mut keys = Vec::with_capacity(nkeys);
+
+        while keys.len() < nkeys {
+            let mut block_len_buf = [0u8; 8];
+            reader.read_exact(&mut block_len_buf)?;
+            let block_len = u64::from_le_bytes(block_len_buf) as usize;
+
+            let mut payload = vec![0u8; block_len];
+            reader.read_exact(&mut payload)?;
+
+            let payload = compression.decompress(&payload, TREE_BLOCK_SIZE * 10);
+
+            let mut cursor = &payload[..];
+            let mut previous = 0u64;
+            while !cursor.is_empty() && keys.len() < nkeys {
+                let delta = read_varint(&mut cursor)?;
+                let morton = previous.wrapping_add(delta);
+                keys.push(MortonKey::from_morton(morton));
+                previous = morton;
+            }
+        }
+
+        Ok(Tree { keys })
+    }
+
     /// Balance a tree, and remove overlaps
     pub fn balance(&self) -> Tree {
 
@@ -126,6 +625,130 @@ impl Tree {
         let linearized = Tree::linearize_keys(balanced);
         Tree{keys: linearized}
     }
+
+    /// Generic bottom-up aggregation: combine `leaf_values` (one summary per occupied leaf) up
+    /// to `ROOT`, level by level from the finest leaves, grouping each level's already-summarized
+    /// keys by `parent()` and folding their summaries into the parent, creating that interior
+    /// entry on demand. The generalization of the old hard-coded `f64` weight accumulation to
+    /// any `Summary` monoid.
+    pub fn aggregate<S: Summary>(&self, leaf_values: &HashMap<MortonKey, S>) -> HashMap<MortonKey, S> {
+        let mut summaries: HashMap<MortonKey, S> = leaf_values.clone();
+
+        for level in (1..=DEEPEST_LEVEL).rev() {
+            let level_keys: Vec<MortonKey> =
+                summaries.keys().filter(|key| key.level() == level).cloned().collect();
+
+            let mut by_parent: HashMap<MortonKey, Vec<S>> = HashMap::new();
+            for key in &level_keys {
+                if key.level() == 0 {
+                    continue;
+                }
+                if let Some(summary) = summaries.get(key) {
+                    by_parent
+                        .entry(key.parent())
+                        .or_insert_with(Vec::new)
+                        .push(summary.clone());
+                }
+            }
+
+            for (parent, children_summaries) in by_parent {
+                summaries.insert(parent, S::combine_all(&children_summaries));
+            }
+        }
+
+        summaries
+    }
+
+    /// Parallel, level-synchronous counterpart to `aggregate`, behind the `rayon` feature:
+    /// each level's keys are processed with `par_iter`, folding per-key contributions into a
+    /// per-parent `Vec<S>` via a parallel fold/reduce before combining, so a level's work runs
+    /// concurrently instead of serially. Kept alongside `aggregate` for correctness comparison.
+    #[cfg(feature = "rayon")]
+    pub fn aggregate_parallel<S: Summary + Send + Sync>(
+        &self,
+        leaf_values: &HashMap<MortonKey, S>,
+    ) -> HashMap<MortonKey, S> {
+        use rayon::prelude::*;
+
+        let mut summaries: HashMap<MortonKey, S> = leaf_values.clone();
+
+        for level in (1..=DEEPEST_LEVEL).rev() {
+            let level_keys: Vec<MortonKey> =
+                summaries.keys().filter(|key| key.level() == level).cloned().collect();
+
+            let by_parent: HashMap<MortonKey, Vec<S>> = level_keys
+                .par_iter()
+                .filter(|key| key.level() > 0)
+                .filter_map(|key| summaries.get(key).map(|summary| (key.parent(), summary.clone())))
+                .fold(HashMap::new, |mut acc: HashMap<MortonKey, Vec<S>>, (parent, summary)| {
+                    acc.entry(parent).or_insert_with(Vec::new).push(summary);
+                    acc
+                })
+                .reduce(HashMap::new, |mut a, b| {
+                    for (key, mut summaries) in b {
+                        a.entry(key).or_insert_with(Vec::new).append(&mut summaries);
+                    }
+                    a
+                });
+
+            for (parent, children_summaries) in by_parent {
+                summaries.insert(parent, S::combine_all(&children_summaries));
+            }
+        }
+
+        summaries
+    }
+
+    /// Coarsen `leaf_values`' key set bottom-up: repeatedly merge a complete set of 8 sibling
+    /// leaves into their parent as long as the parent's combined summary still satisfies
+    /// `predicate`, the generalization of the old `NCRIT`-based `split_blocks`/`merge_blocks`
+    /// check to any `Summary` monoid. Returns the coarsened key set as a new, linearized `Tree`.
+    pub fn coarsen_by<S, P>(leaf_values: &HashMap<MortonKey, S>, predicate: P) -> Tree
+    where
+        S: Summary,
+        P: Fn(&S) -> bool,
+    {
+        let mut current: HashMap<MortonKey, S> = leaf_values.clone();
+
+        loop {
+            let mut by_parent: HashMap<MortonKey, Vec<(MortonKey, S)>> = HashMap::new();
+            for (key, summary) in &current {
+                if key.level() > 0 {
+                    by_parent
+                        .entry(key.parent())
+                        .or_insert_with(Vec::new)
+                        .push((*key, summary.clone()));
+                }
+            }
+
+            let mut coarsened = false;
+
+            for (parent, children) in by_parent {
+                if children.len() < 8 {
+                    continue;
+                }
+
+                let child_summaries: Vec<S> = children.iter().map(|(_, s)| s.clone()).collect();
+                let combined = S::combine_all(&child_summaries);
+
+                if predicate(&combined) {
+                    for (child, _) in &children {
+                        current.remove(child);
+                    }
+                    current.insert(parent, combined);
+                    coarsened = true;
+                }
+            }
+
+            if !coarsened {
+                break;
+            }
+        }
+
+        let mut keys: Vec<MortonKey> = current.keys().cloned().collect();
+        keys.sort();
+        Tree { keys: Tree::linearize_keys(keys) }
+    }
 }
 
 impl Deref for Tree {
@@ -257,4 +880,81 @@ mod tests {
             assert!(a <= b);
         }
     }
+
+    #[cfg(feature = "rayon")]
+    #[derive(Clone)]
+    struct CountSummary(usize);
+
+    #[cfg(feature = "rayon")]
+    impl Summary for CountSummary {
+        fn leaf_summary(_point: &Point) -> Self {
+            CountSummary(1)
+        }
+
+        fn identity() -> Self {
+            CountSummary(0)
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            CountSummary(self.0 + other.0)
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_aggregate_parallel_matches_serial() {
+        let mut tree = tree_fixture();
+        tree.linearize();
+
+        let leaf_values: HashMap<MortonKey, CountSummary> = tree
+            .iter()
+            .cloned()
+            .map(|key| (key, CountSummary(1)))
+            .collect();
+
+        let serial = tree.aggregate(&leaf_values);
+        let parallel = tree.aggregate_parallel(&leaf_values);
+
+        assert_eq!(serial.len(), parallel.len());
+        for (key, summary) in serial.iter() {
+            assert_eq!(summary.0, parallel.get(key).unwrap().0);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_complete_region_parallel_matches_serial() {
+        let a: MortonKey = MortonKey { anchor: [0, 0, 0], morton: 16};
+        let b: MortonKey = MortonKey {anchor: [65535, 65535, 65535], morton: 0b111111111111111111111111111111111111111111111111000000000010000};
+
+        let serial = Tree::complete_region(&a, &b);
+        let parallel = Tree::complete_region_parallel(&a, &b);
+
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn test_insert_then_remove_is_noop() {
+        let mut tree = tree_fixture();
+        tree.linearize();
+        tree.complete();
+
+        // Removing a leaf from a complete tree and re-completing the gap it leaves reconstructs
+        // that same leaf, since the minimal completion between its two neighbors is unique.
+        let before = tree.keys.clone();
+        let removed = before[before.len() / 2];
+        tree.remove(&removed);
+        assert_eq!(tree.keys, before);
+
+        // Inserting a key finer than an existing leaf replaces that leaf and fills the rest of
+        // its span, keeping the key set sorted and ancestor-free.
+        let child = before[before.len() / 2].children()[0];
+        tree.insert(child);
+
+        for i in 0..tree.len() - 1 {
+            assert!(tree.keys[i] <= tree.keys[i + 1]);
+            assert!(!tree.keys[i].is_ancestor(&tree.keys[i + 1]));
+        }
+        assert!(tree.keys.contains(&child));
+    }
 }
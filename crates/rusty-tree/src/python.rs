@@ -0,0 +1,286 @@
+//! pyo3 Python bindings mirroring the C API in `c_api/`.
+//!
+//! `c_api/` exposes `MortonKey`, `Point`, `Tree` and `DistributedTree` as a raw `#[no_mangle]` C
+//! ABI, which leaves Python callers hand-writing `ctypes` bindings and managing Rust-owned
+//! pointers themselves. `PyMortonKey`/`PyTree`/`PyDistributedTree` wrap the same types as
+//! `#[pyclass]`es instead: every method here takes and returns plain Python values (tuples,
+//! lists, NumPy arrays) by copying through them, so a caller never sees a raw pointer.
+
+use numpy::{PyArray1, PyArray2, PyReadonlyArray2, ToPyArray};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{
+    distributed::DistributedTree,
+    single_node::Tree,
+    types::{
+        domain::Domain,
+        morton::MortonKey,
+        point::{Point, PointType},
+    },
+};
+
+/// A point's bounding-box domain, required by most `MortonKey`/`Tree` constructors.
+#[pyclass(name = "Domain")]
+#[derive(Clone)]
+pub struct PyDomain {
+    pub(crate) inner: Domain,
+}
+
+#[pymethods]
+impl PyDomain {
+    #[new]
+    fn new(origin: [PointType; 3], diameter: [PointType; 3]) -> Self {
+        PyDomain {
+            inner: Domain { origin, diameter },
+        }
+    }
+
+    #[getter]
+    fn origin(&self) -> [PointType; 3] {
+        self.inner.origin
+    }
+
+    #[getter]
+    fn diameter(&self) -> [PointType; 3] {
+        self.inner.diameter
+    }
+}
+
+/// A single octree node, identified by its Morton (Z-order) encoding.
+#[pyclass(name = "MortonKey")]
+#[derive(Clone, Copy)]
+pub struct PyMortonKey {
+    pub(crate) inner: MortonKey,
+}
+
+#[pymethods]
+impl PyMortonKey {
+    #[staticmethod]
+    fn from_anchor(anchor: [u64; 3]) -> Self {
+        PyMortonKey {
+            inner: MortonKey::from_anchor(&anchor),
+        }
+    }
+
+    #[staticmethod]
+    fn from_morton(morton: u64) -> Self {
+        PyMortonKey {
+            inner: MortonKey::from_morton(morton),
+        }
+    }
+
+    #[staticmethod]
+    fn from_point(point: [PointType; 3], domain: &PyDomain) -> Self {
+        PyMortonKey {
+            inner: MortonKey::from_point(&point, &domain.inner),
+        }
+    }
+
+    fn parent(&self) -> Self {
+        PyMortonKey {
+            inner: self.inner.parent(),
+        }
+    }
+
+    fn children(&self) -> Vec<PyMortonKey> {
+        self.inner
+            .children()
+            .into_iter()
+            .map(|child| PyMortonKey { inner: child })
+            .collect()
+    }
+
+    fn is_ancestor(&self, other: &PyMortonKey) -> bool {
+        self.inner.is_ancestor(&other.inner)
+    }
+
+    /// The neighbour reached by walking `direction` (in units of this key's own box size) away
+    /// from this key, or `None` if that neighbour would fall outside the root domain.
+    fn find_key_in_direction(&self, direction: [i64; 3]) -> Option<PyMortonKey> {
+        self.inner
+            .find_key_in_direction(&direction)
+            .map(|key| PyMortonKey { inner: key })
+    }
+
+    fn to_coordinates<'py>(&self, py: Python<'py>, domain: &PyDomain) -> Bound<'py, PyArray1<PointType>> {
+        self.inner.to_coordinates(&domain.inner).to_pyarray(py)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MortonKey(morton={}, level={})",
+            self.inner.morton(),
+            self.inner.level()
+        )
+    }
+
+    fn __eq__(&self, other: &PyMortonKey) -> bool {
+        self.inner == other.inner
+    }
+
+    fn __hash__(&self) -> u64 {
+        self.inner.morton()
+    }
+}
+
+/// Copy an `(N, 3)` NumPy array of coordinates out into owned `[PointType; 3]` rows, rejecting
+/// anything that isn't shaped like a point cloud.
+fn coordinates_from_numpy(points: PyReadonlyArray2<PointType>) -> PyResult<Vec<[PointType; 3]>> {
+    let points = points.as_array();
+    if points.shape()[1] != 3 {
+        return Err(PyValueError::new_err(
+            "expected an (N, 3) array of coordinates",
+        ));
+    }
+
+    Ok(points
+        .rows()
+        .into_iter()
+        .map(|row| [row[0], row[1], row[2]])
+        .collect())
+}
+
+/// A single-node, linearized, complete octree over a fixed point set.
+#[pyclass(name = "Tree")]
+pub struct PyTree {
+    inner: Tree,
+    points: Vec<Point>,
+    domain: Domain,
+}
+
+#[pymethods]
+impl PyTree {
+    /// Build a complete, linearized tree directly from an `(N, 3)` NumPy array of coordinates,
+    /// encoding each row to its finest `MortonKey` under `domain` before linearizing/completing
+    /// the resulting key set the same way `Tree`'s own tests build a fixture tree.
+    #[staticmethod]
+    fn from_coordinates(points: PyReadonlyArray2<PointType>, domain: &PyDomain) -> PyResult<Self> {
+        let coordinates = coordinates_from_numpy(points)?;
+
+        let points: Vec<Point> = coordinates
+            .into_iter()
+            .enumerate()
+            .map(|(global_idx, coordinate)| Point {
+                coordinate,
+                global_idx,
+                key: MortonKey::from_point(&coordinate, &domain.inner),
+            })
+            .collect();
+
+        let mut inner = Tree {
+            keys: points.iter().map(|point| point.key).collect(),
+        };
+        inner.linearize();
+        inner.complete();
+
+        Ok(PyTree {
+            inner,
+            points,
+            domain: domain.inner.clone(),
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.keys.len()
+    }
+
+    fn keys(&self) -> Vec<PyMortonKey> {
+        self.inner
+            .keys
+            .iter()
+            .map(|&key| PyMortonKey { inner: key })
+            .collect()
+    }
+
+    fn coordinates<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<PointType>> {
+        points_to_pyarray(py, &self.points)
+    }
+
+    /// The `k` points closest to `query`, ascending by distance.
+    fn knn<'py>(&self, py: Python<'py>, query: [PointType; 3], k: usize) -> Bound<'py, PyArray2<PointType>> {
+        let matches = self.inner.knn(&self.points, &self.domain, query, k);
+        points_to_pyarray(py, &matches)
+    }
+
+    /// Every point within `radius` of `query`, ascending by distance.
+    fn points_within_radius<'py>(
+        &self,
+        py: Python<'py>,
+        query: [PointType; 3],
+        radius: PointType,
+    ) -> Bound<'py, PyArray2<PointType>> {
+        let matches = self
+            .inner
+            .points_within_radius(&self.points, &self.domain, query, radius);
+        points_to_pyarray(py, &matches)
+    }
+}
+
+/// Stack `points`' coordinates into an `(N, 3)` NumPy array.
+fn points_to_pyarray<'py>(py: Python<'py>, points: &[Point]) -> Bound<'py, PyArray2<PointType>> {
+    let flat: Vec<PointType> = points.iter().flat_map(|point| point.coordinate).collect();
+    let array = ndarray::Array2::from_shape_vec((points.len(), 3), flat)
+        .expect("point coordinates always flatten to exactly 3 columns");
+    array.to_pyarray(py)
+}
+
+/// A single-process `DistributedTree`, for parity testing against the MPI-distributed tree
+/// without a Python caller having to manage a communicator of their own.
+#[pyclass(name = "DistributedTree")]
+pub struct PyDistributedTree {
+    inner: DistributedTree,
+    // Kept alive for as long as `inner`'s communicator is in use; never read directly.
+    #[allow(dead_code)]
+    universe: mpi::environment::Universe,
+}
+
+#[pymethods]
+impl PyDistributedTree {
+    /// Build a (single-process) distributed tree directly from an `(N, 3)` NumPy array of
+    /// coordinates, initializing its own one-rank MPI world.
+    #[staticmethod]
+    fn from_coordinates(points: PyReadonlyArray2<PointType>, balanced: bool) -> PyResult<Self> {
+        use mpi::traits::Communicator;
+
+        let coordinates = coordinates_from_numpy(points)?;
+
+        let universe = mpi::initialize()
+            .ok_or_else(|| PyValueError::new_err("MPI is already initialized on this process"))?;
+        let world = universe.world().duplicate();
+
+        let inner = DistributedTree::new(&coordinates, balanced, &world);
+
+        Ok(PyDistributedTree { inner, universe })
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.keys.len()
+    }
+
+    fn keys(&self) -> Vec<PyMortonKey> {
+        self.inner
+            .keys
+            .iter()
+            .map(|&key| PyMortonKey { inner: key })
+            .collect()
+    }
+
+    fn coordinates<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<PointType>> {
+        points_to_pyarray(py, &self.inner.points)
+    }
+
+    fn balanced(&self) -> bool {
+        self.inner.balanced
+    }
+}
+
+/// The `rusty_tree` Python extension module.
+#[pymodule]
+fn rusty_tree(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyDomain>()?;
+    m.add_class::<PyMortonKey>()?;
+    m.add_class::<PyTree>()?;
+    m.add_class::<PyDistributedTree>()?;
+    Ok(())
+}
@@ -0,0 +1,300 @@
+//! Spatial query API over a `DistributedTree`: k-nearest-neighbour and radius searches.
+//!
+//! Both queries walk the local octree top-down from `ROOT`, maintaining a best-distance bound
+//! and pruning any `MortonKey` box whose closest-point distance to the query exceeds it,
+//! descending children in order of increasing box proximity first — the usual branch-and-bound
+//! traversal for octree neighbour queries. To cover the whole distributed tree, each rank also
+//! forwards the query to every other rank and checks, before searching, whether that rank's own
+//! partition (bounded by its local leaf boxes) can plausibly hold a point within the current
+//! bound.
+
+use std::collections::HashSet;
+
+use mpi::{topology::Rank, topology::UserCommunicator, traits::*};
+
+use crate::{
+    constants::ROOT,
+    distributed::DistributedTree,
+    types::{
+        domain::Domain,
+        morton::MortonKey,
+        point::{Point, PointType},
+    },
+};
+
+/// Squared Euclidean distance from `point` to its closest point on `key`'s bounding box, 0 if
+/// `point` lies inside the box.
+fn box_distance_squared(key: &MortonKey, point: &[PointType; 3], domain: &Domain) -> PointType {
+    let corners = key.box_coordinates(domain);
+
+    let mut min = [PointType::INFINITY; 3];
+    let mut max = [PointType::NEG_INFINITY; 3];
+    for corner in corners.chunks(3) {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(corner[axis]);
+            max[axis] = max[axis].max(corner[axis]);
+        }
+    }
+
+    bbox_distance_squared(&min, &max, point)
+}
+
+/// Squared Euclidean distance from `point` to its closest point on the axis-aligned box
+/// `[min, max]`, 0 if `point` lies inside the box.
+fn bbox_distance_squared(min: &[PointType; 3], max: &[PointType; 3], point: &[PointType; 3]) -> PointType {
+    let mut distance_squared = 0.0;
+    for axis in 0..3 {
+        let d = if point[axis] < min[axis] {
+            min[axis] - point[axis]
+        } else if point[axis] > max[axis] {
+            point[axis] - max[axis]
+        } else {
+            0.0
+        };
+        distance_squared += d * d;
+    }
+    distance_squared
+}
+
+/// Squared Euclidean distance between two points.
+fn point_distance_squared(a: &[PointType; 3], b: &[PointType; 3]) -> PointType {
+    (0..3).map(|axis| (a[axis] - b[axis]).powi(2)).sum()
+}
+
+/// `key`'s children whose subtree spans one of `leaves` (this rank's actual leaf keys), sorted by
+/// decreasing box distance to `point`, so a stack they are pushed onto pops the nearest first.
+fn ordered_children(key: &MortonKey, point: &[PointType; 3], domain: &Domain, leaves: &HashSet<MortonKey>) -> Vec<MortonKey> {
+    let mut children: Vec<MortonKey> = key
+        .children()
+        .into_iter()
+        .filter(|child| leaves.iter().any(|leaf| child == leaf || child.is_ancestor(leaf)))
+        .collect();
+
+    children.sort_by(|a, b| {
+        box_distance_squared(b, point, domain)
+            .partial_cmp(&box_distance_squared(a, point, domain))
+            .unwrap()
+    });
+
+    children
+}
+
+impl DistributedTree {
+    /// The axis-aligned box bounding every leaf this rank holds, used to cheaply reject a
+    /// forwarded query before searching this rank's points.
+    fn local_bounds(&self) -> ([PointType; 3], [PointType; 3]) {
+        let mut min = [PointType::INFINITY; 3];
+        let mut max = [PointType::NEG_INFINITY; 3];
+
+        for key in &self.keys {
+            let corners = key.box_coordinates(&self.domain);
+            for corner in corners.chunks(3) {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(corner[axis]);
+                    max[axis] = max[axis].max(corner[axis]);
+                }
+            }
+        }
+
+        (min, max)
+    }
+
+    /// This rank's `k` closest local points to `point`, branch-and-bound descending `ROOT`'s
+    /// children in order of box proximity and shrinking the prune bound to the current k-th
+    /// best distance as matches accumulate.
+    fn knn_local(&self, point: &[PointType; 3], k: usize) -> Vec<(PointType, Point)> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let leaves: HashSet<MortonKey> = self.keys.iter().cloned().collect();
+        let mut best: Vec<(PointType, Point)> = Vec::new();
+        let mut stack = vec![ROOT];
+
+        while let Some(key) = stack.pop() {
+            let bound = if best.len() >= k {
+                best.last().unwrap().0
+            } else {
+                PointType::INFINITY
+            };
+
+            if box_distance_squared(&key, point, &self.domain) > bound {
+                continue;
+            }
+
+            if leaves.contains(&key) {
+                for p in self.points.iter().filter(|p| p.key == key) {
+                    let distance_squared = point_distance_squared(&p.coordinate, point);
+                    best.push((distance_squared, *p));
+                }
+                best.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                best.truncate(k);
+                continue;
+            }
+
+            stack.extend(ordered_children(&key, point, &self.domain, &leaves));
+        }
+
+        best
+    }
+
+    /// This rank's local points within `radius_squared` of `point`, branch-and-bound descending
+    /// `ROOT`'s children in order of box proximity and pruning any subtree whose box distance
+    /// exceeds the (fixed) radius.
+    fn radius_local(&self, point: &[PointType; 3], radius_squared: PointType) -> Vec<Point> {
+        let leaves: HashSet<MortonKey> = self.keys.iter().cloned().collect();
+        let mut matches = Vec::new();
+        let mut stack = vec![ROOT];
+
+        while let Some(key) = stack.pop() {
+            if box_distance_squared(&key, point, &self.domain) > radius_squared {
+                continue;
+            }
+
+            if leaves.contains(&key) {
+                matches.extend(self.points.iter().filter(|p| {
+                    p.key == key && point_distance_squared(&p.coordinate, point) <= radius_squared
+                }));
+                continue;
+            }
+
+            stack.extend(ordered_children(&key, point, &self.domain, &leaves));
+        }
+
+        matches
+    }
+
+    /// Find the `k` points (with their `global_idx`) across the whole distributed tree closest
+    /// to `point`.
+    ///
+    /// Each rank runs `knn_local` against its own share of the tree, then forwards `point` to
+    /// every other rank. A rank only searches a forwarded query if its `local_bounds` box could
+    /// hold a point within the requester's current k-th best distance, and replies with whatever
+    /// it finds; the requester merges all replies with its own local matches and keeps the `k`
+    /// closest.
+    pub fn knn(&self, world: &UserCommunicator, point: [PointType; 3], k: usize) -> Vec<Point> {
+        let rank = world.rank();
+        let size = world.size();
+
+        let mut best = self.knn_local(&point, k);
+        let (local_min, local_max) = self.local_bounds();
+
+        for other in 0..size {
+            if other == rank {
+                continue;
+            }
+            let bound = if best.len() >= k {
+                best.last().map(|(d, _)| *d).unwrap_or(PointType::INFINITY)
+            } else {
+                PointType::INFINITY
+            };
+            world.process_at_rank(other).send(&point);
+            world.process_at_rank(other).send(&bound);
+        }
+
+        for other in 0..size {
+            if other == rank {
+                continue;
+            }
+            let mut their_point = [0 as PointType; 3];
+            let mut their_bound = 0 as PointType;
+            world.process_at_rank(other).receive_into(&mut their_point);
+            world.process_at_rank(other).receive_into(&mut their_bound);
+
+            let feasible = bbox_distance_squared(&local_min, &local_max, &their_point) <= their_bound;
+            let local_matches = if feasible {
+                self.knn_local(&their_point, k)
+            } else {
+                Vec::new()
+            };
+
+            let nmatches = local_matches.len() as Rank;
+            world.process_at_rank(other).send(&nmatches);
+            if nmatches > 0 {
+                let distances: Vec<PointType> = local_matches.iter().map(|(d, _)| *d).collect();
+                let points: Vec<Point> = local_matches.iter().map(|(_, p)| *p).collect();
+                world.process_at_rank(other).send(&distances[..]);
+                world.process_at_rank(other).send(&points[..]);
+            }
+        }
+
+        for other in 0..size {
+            if other == rank {
+                continue;
+            }
+            let mut nmatches: Rank = 0;
+            world.process_at_rank(other).receive_into(&mut nmatches);
+            if nmatches > 0 {
+                let mut distances = vec![0 as PointType; nmatches as usize];
+                let mut points = vec![Point::default(); nmatches as usize];
+                world.process_at_rank(other).receive_into(&mut distances[..]);
+                world.process_at_rank(other).receive_into(&mut points[..]);
+                best.extend(distances.into_iter().zip(points.into_iter()));
+            }
+        }
+
+        best.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        best.truncate(k);
+
+        best.into_iter().map(|(_, p)| p).collect()
+    }
+
+    /// Find every point (with its `global_idx`) across the whole distributed tree within
+    /// `radius` of `point`.
+    ///
+    /// Follows the same forward-and-merge shape as `knn`, except the bound is the fixed
+    /// `radius` rather than a shrinking k-th best distance, so every other rank whose
+    /// `local_bounds` box intersects the query ball is asked unconditionally.
+    pub fn radius(&self, world: &UserCommunicator, point: [PointType; 3], radius: PointType) -> Vec<Point> {
+        let rank = world.rank();
+        let size = world.size();
+        let radius_squared = radius * radius;
+
+        let mut matches = self.radius_local(&point, radius_squared);
+        let (local_min, local_max) = self.local_bounds();
+
+        for other in 0..size {
+            if other == rank {
+                continue;
+            }
+            world.process_at_rank(other).send(&point);
+        }
+
+        for other in 0..size {
+            if other == rank {
+                continue;
+            }
+            let mut their_point = [0 as PointType; 3];
+            world.process_at_rank(other).receive_into(&mut their_point);
+
+            let feasible =
+                bbox_distance_squared(&local_min, &local_max, &their_point) <= radius_squared;
+            let local_matches = if feasible {
+                self.radius_local(&their_point, radius_squared)
+            } else {
+                Vec::new()
+            };
+
+            let nmatches = local_matches.len() as Rank;
+            world.process_at_rank(other).send(&nmatches);
+            if nmatches > 0 {
+                world.process_at_rank(other).send(&local_matches[..]);
+            }
+        }
+
+        for other in 0..size {
+            if other == rank {
+                continue;
+            }
+            let mut nmatches: Rank = 0;
+            world.process_at_rank(other).receive_into(&mut nmatches);
+            if nmatches > 0 {
+                let mut buffer = vec![Point::default(); nmatches as usize];
+                world.process_at_rank(other).receive_into(&mut buffer[..]);
+                matches.extend(buffer);
+            }
+        }
+
+        matches
+    }
+}
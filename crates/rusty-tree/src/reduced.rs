@@ -0,0 +1,125 @@
+//! Reduced-tree representation that collapses single-child chains.
+//!
+//! Traversal and interaction-list construction over a `Tree` waste work descending long chains
+//! of boxes that have exactly one non-empty child — common once `complete`/`balance` fill in
+//! every ancestor of a sparse leaf set. Borrowing the reduced-tree idea from fork-choice
+//! implementations (which collapse single-child chains between branch blocks the same way),
+//! `Tree::reduce` keeps only `ROOT` and nodes that are leaves or have two or more occupied children,
+//! re-parenting every spliced-out node's single occupied child onto the nearest surviving
+//! ancestor, so a solver's `children`/`parent` descent is O(branch nodes) rather than O(all
+//! boxes in the completed tree).
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    constants::ROOT,
+    octree::Tree,
+    types::morton::MortonKey,
+};
+
+/// A node retained in a `ReducedTree`: a leaf, or an interior node with two or more occupied
+/// children.
+#[derive(Debug, Default, Clone)]
+pub struct ReducedNode {
+    /// The nearest retained ancestor, `None` only for `ReducedTree::root`.
+    pub parent: Option<MortonKey>,
+
+    /// This node's retained children, i.e. the occupied children reached after splicing out any
+    /// intervening single-child chain.
+    pub children: Vec<MortonKey>,
+
+    /// The single-child interior nodes spliced out between `parent` and this node, nearest
+    /// ancestor first.
+    pub skipped: Vec<MortonKey>,
+}
+
+/// The reduced tree produced by `Tree::reduce`: a `MortonKey -> ReducedNode` map covering every
+/// leaf and every branch node (a node with two or more occupied children) in a `Tree`.
+pub struct ReducedTree {
+    nodes: HashMap<MortonKey, ReducedNode>,
+    pub root: MortonKey,
+}
+
+impl ReducedTree {
+    /// The retained children of `key`, i.e. the next branch nodes (or leaves) to descend into
+    /// from `key`, skipping any collapsed single-child chain.
+    pub fn children(&self, key: &MortonKey) -> &[MortonKey] {
+        self.nodes
+            .get(key)
+            .map(|node| node.children.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The nearest retained ancestor of `key`, or `None` if `key` is `self.root`.
+    pub fn parent(&self, key: &MortonKey) -> Option<MortonKey> {
+        self.nodes.get(key).and_then(|node| node.parent)
+    }
+
+    /// The single-child chain spliced out between `key` and its retained parent.
+    pub fn skipped(&self, key: &MortonKey) -> &[MortonKey] {
+        self.nodes
+            .get(key)
+            .map(|node| node.skipped.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Whether `key` was retained, i.e. is `ROOT`, a leaf, or a branch node with two or more
+    /// occupied children.
+    pub fn contains(&self, key: &MortonKey) -> bool {
+        self.nodes.contains_key(key)
+    }
+}
+
+impl Tree {
+    /// Build this tree's `ReducedTree`: start from the linearized leaf set, mark every occupied
+    /// interior node (an ancestor of some leaf) with two or more occupied children as a branch
+    /// node, then splice out every other interior node, re-parenting its single occupied child
+    /// onto the closest surviving ancestor.
+    pub fn reduce(&self) -> ReducedTree {
+        let leaves: HashSet<MortonKey> = self.keys.iter().cloned().collect();
+
+        // Every node with at least one leaf descendant (or that is itself a leaf).
+        let mut occupied: HashSet<MortonKey> = HashSet::new();
+        for leaf in &leaves {
+            occupied.insert(*leaf);
+            occupied.extend(leaf.ancestors());
+        }
+
+        let is_retained = |key: &MortonKey| -> bool {
+            leaves.contains(key)
+                || *key == ROOT
+                || key
+                    .children()
+                    .into_iter()
+                    .filter(|child| occupied.contains(child))
+                    .count()
+                    >= 2
+        };
+
+        let retained: Vec<MortonKey> = occupied.iter().cloned().filter(is_retained).collect();
+
+        let mut nodes: HashMap<MortonKey, ReducedNode> = retained
+            .iter()
+            .map(|&key| (key, ReducedNode::default()))
+            .collect();
+
+        for &key in &retained {
+            if key == ROOT {
+                continue;
+            }
+
+            let mut skipped = Vec::new();
+            let mut ancestor = key.parent();
+            while !is_retained(&ancestor) && ancestor.level() > 0 {
+                skipped.push(ancestor);
+                ancestor = ancestor.parent();
+            }
+
+            nodes.get_mut(&key).unwrap().parent = Some(ancestor);
+            nodes.get_mut(&key).unwrap().skipped = skipped;
+            nodes.get_mut(&ancestor).unwrap().children.push(key);
+        }
+
+        ReducedTree { nodes, root: ROOT }
+    }
+}
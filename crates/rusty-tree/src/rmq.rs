@@ -0,0 +1,233 @@
+//! O(1) range nearest-common-ancestor queries over a rank's sorted leaf keys.
+//!
+//! `find_seeds` and the repeated `Tree::complete_region` calls it used to lean on recompute
+//! ancestor relationships pairwise, which gets expensive over a large local leaf set. `LcpIndex`
+//! instead builds a "common-prefix-length" array over the sorted leaves — `lcp[k]` is the level
+//! of `keys[k]`/`keys[k+1]`'s nearest common ancestor — and a sparse-table RMQ over it, so the
+//! ancestor enclosing any contiguous range of leaves is available in O(1) after one O(n log n)
+//! build. Because `lcp` is keyed off `finest_ancestor`, every value already falls on an octree
+//! level boundary (`MortonKey::level()`'s unit), so the range-minimum stays on that grid without
+//! any extra bit-masking.
+
+use crate::{constants::DEEPEST_LEVEL, types::morton::{KeyType, MortonKey}};
+
+/// Walk `key` up to `level` via repeated `parent()` calls, the same truncation
+/// `assign_nodes_to_leaves` does one step at a time while searching a leaf's ancestors.
+fn ancestor_at_level(key: &MortonKey, level: KeyType) -> MortonKey {
+    let mut current = *key;
+    while current.level() > level {
+        current = current.parent();
+    }
+    current
+}
+
+/// A sparse table over `lcp`, answering `min(lcp[l..=r])` in O(1) after an O(n log n) build —
+/// the standard RMQ construction, specialized to the `KeyType` levels `lcp` holds.
+struct SparseTable {
+    /// `table[k][i] = min(lcp[i..i + 2^k])`.
+    table: Vec<Vec<KeyType>>,
+}
+
+impl SparseTable {
+    fn build(lcp: &[KeyType]) -> Self {
+        let n = lcp.len();
+        if n == 0 {
+            return SparseTable { table: Vec::new() };
+        }
+
+        let levels = (n as f64).log2().floor() as usize + 1;
+        let mut table = vec![lcp.to_vec()];
+
+        for k in 1..levels {
+            let span = 1usize << k;
+            let half = 1usize << (k - 1);
+            let mut row = Vec::with_capacity(n - span + 1);
+            for i in 0..=(n - span) {
+                row.push(table[k - 1][i].min(table[k - 1][i + half]));
+            }
+            table.push(row);
+        }
+
+        SparseTable { table }
+    }
+
+    /// Minimum over the inclusive range `[l, r]`.
+    fn query(&self, l: usize, r: usize) -> KeyType {
+        let span = r - l + 1;
+        let k = (span as f64).log2().floor() as usize;
+        let half = 1usize << k;
+        self.table[k][l].min(self.table[k][r + 1 - half])
+    }
+}
+
+/// Sorted leaf keys plus the `lcp`/RMQ structure answering range-ancestor queries over them.
+///
+/// Must be rebuilt (via `build`) whenever the underlying key set changes shape — in particular
+/// after any `Tree::linearize`, which can insert, drop, or reorder keys — since it holds its own
+/// copy of the sorted array rather than a reference that could otherwise go stale.
+pub struct LcpIndex {
+    keys: Vec<MortonKey>,
+    table: SparseTable,
+}
+
+impl LcpIndex {
+    /// Build the index over `leaves`. `leaves` need not already be sorted or deduplicated; this
+    /// sorts and dedups its own copy before computing `lcp`.
+    pub fn build(leaves: &[MortonKey]) -> LcpIndex {
+        let mut keys = leaves.to_vec();
+        keys.sort();
+        keys.dedup();
+
+        let lcp: Vec<KeyType> = keys
+            .windows(2)
+            .map(|pair| pair[0].finest_ancestor(&pair[1]).level())
+            .collect();
+
+        LcpIndex {
+            keys,
+            table: SparseTable::build(&lcp),
+        }
+    }
+
+    /// This index's sorted, deduplicated leaf keys.
+    pub fn keys(&self) -> &[MortonKey] {
+        &self.keys
+    }
+
+    /// The coarsest octant enclosing every leaf in `keys()[i..=j]`: `keys()[i]` itself if
+    /// `i == j`, otherwise `keys()[i]` truncated to `min(lcp[i..j])` — the level of the weakest
+    /// (coarsest) nearest-common-ancestor link inside the range, which is always an upper bound
+    /// on how coarse an octant can cover every leaf in it.
+    pub fn coarsest_enclosing_ancestor(&self, i: usize, j: usize) -> MortonKey {
+        if i == j {
+            return self.keys[i];
+        }
+        let level = self.table.query(i, j - 1);
+        ancestor_at_level(&self.keys[i], level)
+    }
+
+    /// The coarsest level achieved by any nearest-common-ancestor link across the whole index,
+    /// i.e. `coarsest_enclosing_ancestor`'s level bound for the full range.
+    fn global_coarsest_level(&self) -> KeyType {
+        if self.keys.len() < 2 {
+            return self.keys.first().map(|k| k.level()).unwrap_or(DEEPEST_LEVEL);
+        }
+        self.table.query(0, self.keys.len() - 2)
+    }
+
+    /// The coarsest octants spanning this index's leaves, split wherever two adjacent leaves'
+    /// nearest common ancestor sits at the index's overall coarsest level — the same boundaries
+    /// `find_seeds` used to locate by completing the whole region and filtering for its coarsest
+    /// level, but read directly off `lcp` instead.
+    pub fn seeds(&self) -> Vec<MortonKey> {
+        let n = self.keys.len();
+        if n <= 1 {
+            return self.keys.clone();
+        }
+
+        let coarsest_level = self
+            .global_coarsest_level()
+            .min(self.keys[0].level())
+            .min(self.keys[n - 1].level());
+
+        let mut splits: Vec<usize> = (0..n - 1)
+            .filter(|&k| self.table.query(k, k) == coarsest_level)
+            .map(|k| k + 1)
+            .collect();
+        splits.push(n);
+
+        // Every seed is truncated to one level below `coarsest_level`: leaves on either side of a
+        // split lie in different children of their common ancestor at `coarsest_level`, so each
+        // block gets its own child octant. Unlike `coarsest_enclosing_ancestor`, a block's own
+        // range-minimum can't be used directly here, since a singleton block's minimum is its
+        // leaf's own (finer) level.
+        let mut seeds = Vec::with_capacity(splits.len());
+        let mut start = 0;
+        for end in splits {
+            seeds.push(ancestor_at_level(&self.keys[start], coarsest_level + 1));
+            start = end;
+        }
+        seeds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+    use rand::SeedableRng;
+
+    use crate::types::domain::Domain;
+
+    fn keys_fixture(seed: u64, npoints: u64) -> Vec<MortonKey> {
+        let domain = Domain {
+            origin: [0., 0., 0.],
+            diameter: [1., 1., 1.],
+        };
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let between = rand::distributions::Uniform::from(0.0..1.0);
+
+        (0..npoints)
+            .map(|_| {
+                let coordinate = [
+                    between.sample(&mut rng),
+                    between.sample(&mut rng),
+                    between.sample(&mut rng),
+                ];
+                MortonKey::from_point(&coordinate, &domain)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_seeds_of_empty_index_is_empty() {
+        let index = LcpIndex::build(&[]);
+        assert!(index.seeds().is_empty());
+    }
+
+    #[test]
+    fn test_seeds_of_single_key_is_itself() {
+        let keys = keys_fixture(0, 1);
+        let index = LcpIndex::build(&keys);
+        assert_eq!(index.seeds(), keys);
+    }
+
+    #[test]
+    fn test_seeds_cover_every_leaf() {
+        let keys = keys_fixture(1, 200);
+        let index = LcpIndex::build(&keys);
+        let seeds = index.seeds();
+
+        for key in index.keys() {
+            assert!(
+                seeds.iter().any(|seed| seed == key || seed.is_ancestor(key)),
+                "no seed covers {key:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_seeds_are_strictly_increasing() {
+        let keys = keys_fixture(2, 200);
+        let index = LcpIndex::build(&keys);
+
+        for pair in index.seeds().windows(2) {
+            assert!(pair[0] < pair[1], "seeds out of order: {pair:?}");
+        }
+    }
+
+    #[test]
+    fn test_coarsest_enclosing_ancestor_covers_whole_range() {
+        let keys = keys_fixture(3, 200);
+        let index = LcpIndex::build(&keys);
+
+        let n = index.keys().len();
+        let ancestor = index.coarsest_enclosing_ancestor(0, n - 1);
+
+        for key in index.keys() {
+            assert!(ancestor == *key || ancestor.is_ancestor(key));
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Generic top-down tree walker / visitor abstraction over a `DistributedTree`.
+//!
+//! `Tree::balance`, `query`'s branch-and-bound search, and any future per-node analysis each
+//! hand-roll the same descent over `children()`/`ancestors()`. `TreeWalker` factors that out into
+//! a pair of callbacks driven by `DistributedTree::walk` (full top-down descent from `ROOT`) or
+//! `LeafWalker::run` (leaves only, skipping the interior nodes entirely). A handful of built-in
+//! visitors — `OccupancyHistogram`, `DepthStatistics`, `InteractionListBuilder` — cover the usual
+//! analyses so callers don't each reimplement the loop.
+
+use std::collections::{HashMap, HashSet};
+
+use mpi::{topology::UserCommunicator, traits::*, Count};
+
+use crate::{
+    constants::ROOT,
+    distributed::DistributedTree,
+    types::morton::{KeyType, MortonKey},
+    types::point::Point,
+};
+
+/// A visitor driven top-down over a `DistributedTree`'s local share of the tree.
+pub trait TreeWalker {
+    /// Called once for every node on the descent, in top-down order, before its children (if
+    /// any) are visited. Return `false` to prune the subtree rooted at `key` — neither its
+    /// children nor `visit_leaf` (if `key` is itself a leaf) are visited.
+    fn visit_node(&mut self, _key: &MortonKey, _level: KeyType) -> bool {
+        true
+    }
+
+    /// Called with a leaf and that leaf's points. `LeafWalker::run` calls it once for each key in
+    /// `tree.keys` that owns at least one of `tree.points`; `DistributedTree::walk` calls it for
+    /// every key in `tree.keys` the descent reaches, with an empty slice if it owns no points.
+    fn visit_leaf(&mut self, _key: &MortonKey, _points: &[Point]) {}
+}
+
+impl DistributedTree {
+    /// Drive `visitor` top-down over this rank's local share of the tree, descending from `ROOT`
+    /// through `children()` the same way `Tree::balance` and `query`'s `knn_local` do, and
+    /// calling `visit_leaf` whenever the descent reaches one of `self.keys`.
+    pub fn walk<W: TreeWalker>(&self, visitor: &mut W) {
+        let leaves: HashSet<MortonKey> = self.keys.iter().cloned().collect();
+        let mut stack = vec![ROOT];
+
+        while let Some(key) = stack.pop() {
+            if !visitor.visit_node(&key, key.level()) {
+                continue;
+            }
+
+            if leaves.contains(&key) {
+                let points: Vec<Point> = self
+                    .points
+                    .iter()
+                    .filter(|p| p.key == key)
+                    .cloned()
+                    .collect();
+                visitor.visit_leaf(&key, &points);
+                continue;
+            }
+
+            stack.extend(key.children());
+        }
+    }
+}
+
+/// Drives a `TreeWalker`'s `visit_leaf` callback directly over `tree.keys`, skipping the
+/// top-down descent through interior nodes that `DistributedTree::walk` does — cheaper when a
+/// visitor only cares about leaves. Each rank runs over its own share of the tree, so applying
+/// the same visitor on every rank already parallelises the walk across the distributed tree;
+/// visitors that need a cross-rank view (e.g. `OccupancyHistogram::global`) gather afterwards.
+pub struct LeafWalker;
+
+impl LeafWalker {
+    /// Run `visitor` over every key in `tree.keys` that owns at least one of `tree.points`.
+    pub fn run<W: TreeWalker>(tree: &DistributedTree, visitor: &mut W) {
+        for key in &tree.keys {
+            let points: Vec<Point> = tree
+                .points
+                .iter()
+                .filter(|p| &p.key == key)
+                .cloned()
+                .collect();
+
+            if !points.is_empty() {
+                visitor.visit_leaf(key, &points);
+            }
+        }
+    }
+}
+
+/// Number of points held by each of this rank's populated leaves, built via `LeafWalker`.
+#[derive(Debug, Default)]
+pub struct OccupancyHistogram {
+    pub counts: HashMap<MortonKey, usize>,
+}
+
+impl TreeWalker for OccupancyHistogram {
+    fn visit_leaf(&mut self, key: &MortonKey, points: &[Point]) {
+        self.counts.insert(*key, points.len());
+    }
+}
+
+impl OccupancyHistogram {
+    /// Gather every rank's local histogram onto root, keyed by leaf, returning the occupancy
+    /// over the whole distributed tree. Non-root ranks get back an empty map.
+    pub fn global(&self, world: &UserCommunicator) -> HashMap<MortonKey, usize> {
+        let comm = world.duplicate();
+        let rank = comm.rank();
+        let size = comm.size();
+        let root_rank = 0;
+        let root_process = comm.process_at_rank(root_rank);
+
+        let local_keys: Vec<MortonKey> = self.counts.keys().cloned().collect();
+        let local_counts: Vec<Count> = self.counts.values().map(|&c| c as Count).collect();
+        let nlocal = local_keys.len() as Count;
+
+        let mut nkeys_by_rank = vec![0 as Count; size as usize];
+        if rank == root_rank {
+            root_process.gather_into_root(&nlocal, &mut nkeys_by_rank[..]);
+        } else {
+            root_process.gather_into(&nlocal);
+        }
+
+        if rank != root_rank {
+            root_process.gather_varcount_into(&local_keys[..]);
+            root_process.gather_varcount_into(&local_counts[..]);
+            return HashMap::new();
+        }
+
+        let displs: Vec<Count> = nkeys_by_rank
+            .iter()
+            .scan(0, |acc, &x| {
+                let tmp = *acc;
+                *acc += x;
+                Some(tmp)
+            })
+            .collect();
+        let total: usize = nkeys_by_rank.iter().sum::<Count>() as usize;
+
+        let mut global_keys = vec![MortonKey::default(); total];
+        let mut global_counts = vec![0 as Count; total];
+        {
+            use mpi::datatype::PartitionMut;
+            let mut key_partition =
+                PartitionMut::new(&mut global_keys[..], nkeys_by_rank.clone(), &displs[..]);
+            root_process.gather_varcount_into_root(&local_keys[..], &mut key_partition);
+
+            let mut count_partition =
+                PartitionMut::new(&mut global_counts[..], nkeys_by_rank, &displs[..]);
+            root_process.gather_varcount_into_root(&local_counts[..], &mut count_partition);
+        }
+
+        global_keys
+            .into_iter()
+            .zip(global_counts.into_iter())
+            .map(|(key, count)| (key, count as usize))
+            .collect()
+    }
+}
+
+/// Minimum, maximum and mean leaf depth (i.e. `MortonKey::level()`) over the leaves a walk
+/// visits, built via `LeafWalker` or `DistributedTree::walk`.
+#[derive(Debug, Clone, Copy)]
+pub struct DepthStatistics {
+    pub min: KeyType,
+    pub max: KeyType,
+    sum: u64,
+    count: u64,
+}
+
+impl Default for DepthStatistics {
+    fn default() -> Self {
+        DepthStatistics {
+            min: KeyType::MAX,
+            max: 0,
+            sum: 0,
+            count: 0,
+        }
+    }
+}
+
+impl TreeWalker for DepthStatistics {
+    fn visit_leaf(&mut self, key: &MortonKey, _points: &[Point]) {
+        let level = key.level();
+        self.min = self.min.min(level);
+        self.max = self.max.max(level);
+        self.sum += level as u64;
+        self.count += 1;
+    }
+}
+
+impl DepthStatistics {
+    /// Mean leaf depth over the leaves this statistic has seen so far.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum as f64 / self.count as f64
+        }
+    }
+
+    /// Combine every rank's local statistics into the depth statistics over the whole
+    /// distributed tree, identical on every rank.
+    pub fn global(&self, world: &UserCommunicator) -> DepthStatistics {
+        let comm = world.duplicate();
+        let size = comm.size();
+
+        let mut mins = vec![KeyType::MAX; size as usize];
+        let mut maxs = vec![0 as KeyType; size as usize];
+        let mut sums = vec![0u64; size as usize];
+        let mut counts = vec![0u64; size as usize];
+
+        comm.all_gather_into(&self.min, &mut mins[..]);
+        comm.all_gather_into(&self.max, &mut maxs[..]);
+        comm.all_gather_into(&self.sum, &mut sums[..]);
+        comm.all_gather_into(&self.count, &mut counts[..]);
+
+        DepthStatistics {
+            min: mins.into_iter().min().unwrap_or(KeyType::MAX),
+            max: maxs.into_iter().max().unwrap_or(0),
+            sum: sums.into_iter().sum(),
+            count: counts.into_iter().sum(),
+        }
+    }
+}
+
+/// Per-leaf interaction (V-)lists: the children of the leaf's parent's colleagues that are not
+/// themselves colleagues of the leaf, i.e. the usual FMM well-separated set built from one level
+/// of `neighbors()` above the leaf.
+#[derive(Debug, Default)]
+pub struct InteractionListBuilder {
+    pub lists: HashMap<MortonKey, Vec<MortonKey>>,
+}
+
+impl TreeWalker for InteractionListBuilder {
+    fn visit_leaf(&mut self, key: &MortonKey, _points: &[Point]) {
+        if key.level() == 0 {
+            self.lists.insert(*key, Vec::new());
+            return;
+        }
+
+        let colleagues: HashSet<MortonKey> = key.neighbors().into_iter().collect();
+        let parent = key.parent();
+
+        let interaction_list: Vec<MortonKey> = parent
+            .neighbors()
+            .into_iter()
+            .flat_map(|colleague| colleague.children())
+            .filter(|candidate| candidate != key && !colleagues.contains(candidate))
+            .collect();
+
+        self.lists.insert(*key, interaction_list);
+    }
+}
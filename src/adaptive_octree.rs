@@ -1,25 +1,153 @@
 //! Data structures and functions for adaptive octrees.
 
-use ndarray::{ArrayView2, Axis};
+use super::helpers::TreeStatistics;
+use ndarray::{concatenate, Array2, ArrayView2, Axis};
 use rayon::prelude::*;
 use rusty_kernel_tools::RealType;
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
 
-pub struct AdaptiveOctree<'a, T: RealType> {
+pub struct AdaptiveOctree<T: RealType> {
     /// A (3, N) array of N particles.
-    pub particles: ArrayView2<'a, T>,
+    ///
+    /// Owned rather than borrowed so `AdaptiveOctree::insert_batch` can grow it in place.
+    pub particles: Array2<T>,
 
     /// The maximum level in the tree.
     pub max_level: usize,
 
+    /// The maximum number of particles in a leaf, kept around so `insert_batch` can tell
+    /// which leaves a later batch of points has made oversized without it being passed in
+    /// again.
+    pub max_particles: usize,
+
     /// The origin of the bounding box for the particles.
     pub origin: [f64; 3],
 
     /// The diameter across each dimension of the bounding box.
     pub diameter: [f64; 3],
 
-    /// Mapping from keys to associated particle indices.
+    /// Mapping from keys to associated particle indices. Unlike `RegularOctree`'s
+    /// `leaf_key_to_particles`, this holds an entry for every key `refine_partition` ever visited
+    /// — leaves and their ancestors alike — since a leaf's particles are also counted in each of
+    /// its ancestors' sets.
     pub keys_to_indices: HashMap<usize, HashSet<usize>>,
+
+    /// The set of near-field keys for each leaf key.
+    pub near_field: HashMap<usize, HashSet<usize>>,
+
+    /// The set of keys in the interaction list for each leaf key.
+    pub interaction_list: HashMap<usize, HashSet<usize>>,
+
+    /// Statistics for the tree.
+    pub statistics: TreeStatistics,
+}
+
+/// Whether `key` has no finer key in `keys_to_indices`, i.e. `refine_partition` never split it
+/// further.
+fn is_leaf(key: usize, keys_to_indices: &HashMap<usize, HashSet<usize>>) -> bool {
+    use super::morton::{find_level, find_parent};
+
+    let level = find_level(key);
+    !keys_to_indices
+        .keys()
+        .any(|&other| find_level(other) == level + 1 && find_parent(other) == key)
+}
+
+/// The leaf keys among `key` and its descendants that are present in `keys_to_indices` — `key`
+/// itself if it's a leaf, otherwise whichever of its children (recursively) are.
+///
+/// Used to resolve a same-level near-field/interaction-list candidate that turned out to have
+/// been split further: the candidate isn't a leaf, but the finer boxes that replaced it are the
+/// ones that actually touch (or are separated from) `key`.
+fn leaf_descendants(key: usize, keys_to_indices: &HashMap<usize, HashSet<usize>>) -> Vec<usize> {
+    use super::morton::{find_level, find_parent};
+
+    if is_leaf(key, keys_to_indices) {
+        return vec![key];
+    }
+
+    let level = find_level(key);
+    let children: Vec<usize> = keys_to_indices
+        .keys()
+        .copied()
+        .filter(|&other| find_level(other) == level + 1 && find_parent(other) == key)
+        .collect();
+
+    children
+        .into_iter()
+        .flat_map(|child| leaf_descendants(child, keys_to_indices))
+        .collect()
+}
+
+/// The nearest ancestor of `key` that is present in `keys_to_indices`, or `None` if `key`'s whole
+/// ancestor chain (up to and including the root) is empty — meaning that region of space holds no
+/// particles at all.
+fn nearest_existing_ancestor(key: usize, keys_to_indices: &HashMap<usize, HashSet<usize>>) -> Option<usize> {
+    use super::morton::{find_level, find_parent};
+
+    let mut level = find_level(key);
+    let mut current = key;
+    while level > 0 {
+        current = find_parent(current);
+        level -= 1;
+        if keys_to_indices.contains_key(&current) {
+            return Some(current);
+        }
+    }
+    None
+}
+
+/// Resolve same-level `candidates` (produced by `compute_near_field`/`compute_interaction_list`)
+/// against the actual, possibly unevenly refined, tree: a candidate that was split further
+/// contributes its leaf descendants instead of itself, and a candidate that was never created
+/// (because that region is empty, or only exists as part of a coarser box) contributes its
+/// nearest existing ancestor instead.
+fn resolve_candidates(
+    candidates: HashSet<usize>,
+    keys_to_indices: &HashMap<usize, HashSet<usize>>,
+) -> HashSet<usize> {
+    let mut resolved = HashSet::new();
+    for candidate in candidates {
+        if keys_to_indices.contains_key(&candidate) {
+            resolved.extend(leaf_descendants(candidate, keys_to_indices));
+        } else if let Some(ancestor) = nearest_existing_ancestor(candidate, keys_to_indices) {
+            resolved.insert(ancestor);
+        }
+    }
+    resolved
+}
+
+/// Build the near-field and interaction-list maps for every leaf key in `keys_to_indices`,
+/// resolving each same-level candidate against the tree's actual (uneven) refinement via
+/// `resolve_candidates`.
+fn compute_near_field_and_interaction_list(
+    keys_to_indices: &HashMap<usize, HashSet<usize>>,
+) -> (HashMap<usize, HashSet<usize>>, HashMap<usize, HashSet<usize>>) {
+    use super::morton::{compute_interaction_list, compute_near_field};
+
+    let leaves: Vec<usize> = keys_to_indices
+        .keys()
+        .copied()
+        .filter(|&key| is_leaf(key, keys_to_indices))
+        .collect();
+
+    let near_field: HashMap<usize, HashSet<usize>> = leaves
+        .par_iter()
+        .map(|&key| (key, resolve_candidates(compute_near_field(key), keys_to_indices)))
+        .collect();
+
+    let interaction_list: HashMap<usize, HashSet<usize>> = leaves
+        .par_iter()
+        .map(|&key| {
+            (
+                key,
+                resolve_candidates(compute_interaction_list(key), keys_to_indices),
+            )
+        })
+        .collect();
+
+    (near_field, interaction_list)
 }
 
 fn refine_partition<T: RealType>(
@@ -101,7 +229,7 @@ fn refine_partition<T: RealType>(
 pub fn adaptive_octree<T: RealType>(
     particles: ArrayView2<T>,
     max_particles: usize,
-) -> AdaptiveOctree<'_, T> {
+) -> AdaptiveOctree<T> {
     use super::helpers::compute_bounds;
 
     const TOL: f64 = 1E-5;
@@ -136,10 +264,12 @@ pub fn adaptive_octree_with_bounding_box<T: RealType>(
     max_particles: usize,
     origin: [f64; 3],
     diameter: [f64; 3],
-) -> AdaptiveOctree<'_, T> {
+) -> AdaptiveOctree<T> {
     use super::morton::find_level;
     let number_of_particles = particles.len_of(Axis(1));
 
+    let now = Instant::now();
+
     let mut keys_to_indices = HashMap::<usize, HashSet<usize>>::new();
     let particle_indices: HashSet<usize> = (0..number_of_particles).collect();
 
@@ -159,34 +289,146 @@ pub fn adaptive_octree_with_bounding_box<T: RealType>(
         .max()
         .unwrap();
 
+    let (near_field, interaction_list) = compute_near_field_and_interaction_list(&keys_to_indices);
+
+    let leaf_particle_counts: Vec<usize> = keys_to_indices
+        .keys()
+        .filter(|&&key| is_leaf(key, &keys_to_indices))
+        .map(|key| keys_to_indices[key].len())
+        .collect();
+
+    let duration = now.elapsed();
+
+    let statistics = TreeStatistics {
+        number_of_particles: number_of_particles,
+        max_level: max_level,
+        number_of_leafs: leaf_particle_counts.len(),
+        number_of_keys: keys_to_indices.len(),
+        creation_time: duration,
+        minimum_number_of_particles_in_leaf: leaf_particle_counts
+            .iter()
+            .copied()
+            .reduce(std::cmp::min)
+            .unwrap(),
+        maximum_number_of_particles_in_leaf: leaf_particle_counts
+            .iter()
+            .copied()
+            .reduce(std::cmp::max)
+            .unwrap(),
+        average_number_of_particles_in_leaf: (leaf_particle_counts.iter().sum::<usize>() as f64)
+            / (leaf_particle_counts.len() as f64),
+    };
+
     AdaptiveOctree {
-        particles: particles,
+        particles: particles.to_owned(),
         max_level: max_level,
+        max_particles: max_particles,
         origin: origin,
         diameter: diameter,
         keys_to_indices: keys_to_indices,
+        near_field: near_field,
+        interaction_list: interaction_list,
+        statistics: statistics,
     }
+}
+
+impl<T: RealType> AdaptiveOctree<T> {
+    /// Fold `new` particles into this tree as a second layer over the existing
+    /// `keys_to_indices` map, instead of re-running `refine_partition` over every particle from
+    /// scratch.
+    ///
+    /// Each new particle is walked down from the root through whichever existing key already
+    /// covers it, so it lands in the same leaf `refine_partition` would have put it in. Any leaf
+    /// that grows past `max_particles` as a result is then split by calling `refine_partition` on
+    /// just that leaf's (now-merged) particle set, the same splitting step the original
+    /// construction uses — unlike `RegularOctree::insert_batch`, which never splits because a
+    /// regular tree's depth is fixed.
+    pub fn insert_batch(&mut self, new: ArrayView2<T>) {
+        use super::morton::{encode_point, find_level};
+
+        let number_of_new = new.len_of(Axis(1));
+        if number_of_new == 0 {
+            return;
+        }
+
+        let first_new_index = self.particles.len_of(Axis(1));
+        self.particles = concatenate(Axis(1), &[self.particles.view(), new]).unwrap();
+
+        let mut touched = HashSet::<usize>::new();
+
+        for local_index in 0..number_of_new {
+            let particle_index = first_new_index + local_index;
+            let particle = [
+                new[[0, local_index]].to_f64().unwrap(),
+                new[[1, local_index]].to_f64().unwrap(),
+                new[[2, local_index]].to_f64().unwrap(),
+            ];
+
+            let mut leaf = 0;
+            loop {
+                // Every key on the way down counts the particle, as `refine_partition` does.
+                self.keys_to_indices
+                    .entry(leaf)
+                    .or_insert_with(HashSet::new)
+                    .insert(particle_index);
+                let level = find_level(leaf);
+                let child = encode_point(&particle, 1 + level, &self.origin, &self.diameter);
+                if self.keys_to_indices.contains_key(&child) {
+                    leaf = child;
+                } else {
+                    break;
+                }
+            }
 
-    // let statistics = TreeStatistics {
-    //     number_of_particles: particles.len_of(Axis(1)),
-    //     max_level: max_level,
-    //     number_of_leafs: leaf_key_to_particles.keys().len(),
-    //     number_of_keys: all_keys.len(),
-    //     creation_time: duration,
-    //     minimum_number_of_particles_in_leaf: leaf_key_to_particles
-    //         .values()
-    //         .map(|item| item.len())
-    //         .reduce(std::cmp::min)
-    //         .unwrap(),
-    //     maximum_number_of_particles_in_leaf: leaf_key_to_particles
-    //         .values()
-    //         .map(|item| item.len())
-    //         .reduce(std::cmp::max)
-    //         .unwrap(),
-    //     average_number_of_particles_in_leaf: (leaf_key_to_particles
-    //         .values()
-    //         .map(|item| item.len())
-    //         .sum::<usize>() as f64)
-    //         / (leaf_key_to_particles.keys().len() as f64),
-    // };
+            touched.insert(leaf);
+        }
+
+        for key in touched {
+            let indices = self.keys_to_indices.remove(&key).unwrap();
+            refine_partition(
+                key,
+                &indices,
+                self.particles.view(),
+                &mut self.keys_to_indices,
+                self.max_particles,
+                &self.origin,
+                &self.diameter,
+            );
+        }
+
+        self.max_level = self
+            .keys_to_indices
+            .keys()
+            .map(|&item| find_level(item))
+            .max()
+            .unwrap();
+
+        let (near_field, interaction_list) =
+            compute_near_field_and_interaction_list(&self.keys_to_indices);
+        self.near_field = near_field;
+        self.interaction_list = interaction_list;
+
+        let leaf_particle_counts: Vec<usize> = self
+            .keys_to_indices
+            .keys()
+            .filter(|&&key| is_leaf(key, &self.keys_to_indices))
+            .map(|key| self.keys_to_indices[key].len())
+            .collect();
+
+        self.statistics.number_of_particles = self.particles.len_of(Axis(1));
+        self.statistics.number_of_leafs = leaf_particle_counts.len();
+        self.statistics.number_of_keys = self.keys_to_indices.len();
+        self.statistics.minimum_number_of_particles_in_leaf = leaf_particle_counts
+            .iter()
+            .copied()
+            .reduce(std::cmp::min)
+            .unwrap_or(0);
+        self.statistics.maximum_number_of_particles_in_leaf = leaf_particle_counts
+            .iter()
+            .copied()
+            .reduce(std::cmp::max)
+            .unwrap_or(0);
+        self.statistics.average_number_of_particles_in_leaf =
+            leaf_particle_counts.iter().sum::<usize>() as f64 / (leaf_particle_counts.len() as f64);
+    }
 }
@@ -269,6 +269,13 @@ pub fn split_blocks(
 }
 
 
+/// Partition `points` across ranks by parallel Morton sample-sort, then refine each rank's
+/// block down to `ncrit` points per leaf.
+///
+/// Returns this rank's local tree (unbalanced — no 2:1 guarantee across rank boundaries) and
+/// the `lower`/`upper` Morton-key range owned by every rank, gathered so each rank can tell
+/// which neighbour owns a given boundary key. See [`balanced_tree`] for the variant that also
+/// imports neighbour-owned boundary keys.
 pub fn unbalanced_tree(
     &ncrit: &usize,
     &size: &Rank,
@@ -277,7 +284,7 @@ pub fn unbalanced_tree(
     points: Vec<[PointType; 3]>,
     domain: &Domain,
     k: Rank,
-) {
+) -> (Vec<MortonKey>, Vec<BlockBounds>) {
 
     let comm = universe.world();
     let mut comm = comm.split_by_color(Color::with_value(0)).unwrap();
@@ -357,12 +364,11 @@ pub fn unbalanced_tree(
     ).linearize();
 
     // 6. Refine blocks based on ncrit
-    let unbalanced_tree = split_blocks(&leaves.keys, blocktree.keys, &ncrit);
+    let unbalanced_assignment = split_blocks(&leaves.keys, blocktree.keys, &ncrit);
 
     let mut blocks_to_points: HashMap<MortonKey, usize> = HashMap::new();
-    let mut new_blocktree: Vec<MortonKey> = Vec::new();
 
-    for (_, block) in unbalanced_tree {
+    for (_, block) in unbalanced_assignment {
 
         if !blocks_to_points.contains_key(&block) {
             blocks_to_points.insert(block.clone(), 1);
@@ -373,9 +379,130 @@ pub fn unbalanced_tree(
         }
     }
 
-    for (block, count) in blocks_to_points {
+    for (&block, &count) in blocks_to_points.iter() {
         assert!(count <= ncrit);
     }
 
+    let local_tree: Vec<MortonKey> = blocks_to_points.into_keys().collect();
 
+    // 7. Gather every rank's owned Morton-key range so boundary keys can be routed to
+    // whichever rank actually owns them.
+    let own_bounds = BlockBounds {
+        rank,
+        lower: *local_tree.iter().min().unwrap(),
+        upper: *local_tree.iter().max().unwrap(),
+    };
+
+    let mut all_bounds = vec![own_bounds.clone(); size as usize];
+    comm.all_gather_into(&own_bounds, &mut all_bounds[..]);
+
+    (local_tree, all_bounds)
+}
+
+/// Build a distributed adaptive octree from `points`, extended across rank boundaries by the
+/// neighbour-owned keys its blocks' near fields reach.
+///
+/// Runs the same Morton sample-sort and per-block `ncrit` refinement as [`unbalanced_tree`],
+/// then exchanges the boundary keys whose near field crosses into a neighbour's owned range
+/// with that neighbour (previous/next rank in SFC order) and completes their ancestors
+/// locally with [`find_completion`], so a rank's own tree never references a near-field key
+/// one of its neighbours has refined past without recording the coarser key in between.
+/// No key is refined further, so the result is not 2:1 balanced.
+///
+/// Returns the per-rank balanced [`Tree`] plus the ownership ranges from [`unbalanced_tree`].
+pub fn balanced_tree(
+    &ncrit: &usize,
+    &size: &Rank,
+    &rank: &Rank,
+    universe: &Universe,
+    points: Vec<[PointType; 3]>,
+    domain: &Domain,
+    k: Rank,
+) -> (Tree, Vec<BlockBounds>) {
+    let (local_tree, bounds) = unbalanced_tree(&ncrit, &size, &rank, universe, points, domain, k);
+
+    let mut all_keys: HashSet<MortonKey> = local_tree.iter().cloned().collect();
+    let own_bounds = &bounds[rank as usize];
+
+    // 8. Any near-field key of a local block that falls outside our own range is owned by
+    // the previous or next rank in SFC order.
+    let mut needed_from_previous: Vec<MortonKey> = Vec::new();
+    let mut needed_from_next: Vec<MortonKey> = Vec::new();
+
+    for &key in &local_tree {
+        for dx in -1..=1i64 {
+            for dy in -1..=1i64 {
+                for dz in -1..=1i64 {
+                    if dx == 0 && dy == 0 && dz == 0 {
+                        continue;
+                    }
+                    if let Some(neighbour) = key.find_key_in_direction(&[dx, dy, dz]) {
+                        if neighbour < own_bounds.lower {
+                            needed_from_previous.push(neighbour);
+                        } else if neighbour > own_bounds.upper {
+                            needed_from_next.push(neighbour);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let comm = universe.world();
+    let previous_rank = if rank > 0 { rank - 1 } else { size - 1 };
+    let next_rank = if rank + 1 < size { rank + 1 } else { 0 };
+
+    // 9. Exchange the requested boundary keys with each neighbour and add, with their
+    // ancestors, whichever of the keys we asked for the neighbour actually holds.
+    let imported_previous =
+        exchange_boundary_keys(&comm, previous_rank, &needed_from_previous, &all_keys);
+    let imported_next = exchange_boundary_keys(&comm, next_rank, &needed_from_next, &all_keys);
+
+    for imported in imported_previous.into_iter().chain(imported_next) {
+        find_completion(imported, &mut all_keys);
+    }
+
+    (Tree { keys: all_keys }, bounds)
+}
+
+/// Send `requested` to `partner_rank`, reply with whichever of the keys they request of us
+/// in return are present in `owned`, and return the keys `partner_rank` sent back to us.
+fn exchange_boundary_keys(
+    comm: &UserCommunicator,
+    partner_rank: Rank,
+    requested: &[MortonKey],
+    owned: &HashSet<MortonKey>,
+) -> Vec<MortonKey> {
+    let partner = comm.process_at_rank(partner_rank);
+
+    let send_size = requested.len() as Rank;
+    partner.send(&send_size);
+    partner.send(requested);
+
+    let mut recv_size: Rank = 0;
+    partner.receive_into(&mut recv_size);
+    let mut their_request = vec![MortonKey::default(); recv_size as usize];
+    partner.receive_into(&mut their_request[..]);
+
+    let reply: Vec<MortonKey> = their_request
+        .into_iter()
+        .filter(|key| owned.contains(key))
+        .collect();
+
+    let reply_size = reply.len() as Rank;
+    partner.send(&reply_size);
+    partner.send(&reply[..]);
+
+    let mut import_size: Rank = 0;
+    partner.receive_into(&mut import_size);
+    let mut imported = vec![MortonKey::default(); import_size as usize];
+    partner.receive_into(&mut imported[..]);
+
+    imported
+}
+
+/// Insert `key` and all of its ancestors into `all_keys`.
+fn find_completion(key: MortonKey, all_keys: &mut HashSet<MortonKey>) {
+    all_keys.insert(key);
+    all_keys.extend(key.ancestors());
 }
\ No newline at end of file
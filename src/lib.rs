@@ -4,5 +4,8 @@ pub mod constants;
 pub mod distribute;
 pub mod helpers;
 pub mod morton;
+pub mod morton_io;
+pub mod morton_text;
 pub mod octree;
-pub mod regular;
\ No newline at end of file
+pub mod regular;
+pub mod retention;
\ No newline at end of file
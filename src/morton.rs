@@ -15,10 +15,22 @@ use mpi::{
 };
 
 use crate::types::Domain;
-use crate::types::KeyType;
 use crate::types::PointType;
 
+/// The integer type `MortonKey` packs its interleaved anchor and level into. `u64` caps
+/// `DEEPEST_LEVEL` at 16 (3 interleaved bits per axis per level, plus the level field, must fit
+/// in 64 bits); opting into `wide-keys` widens it to `u128` and `DEEPEST_LEVEL` to 40, for trees
+/// over point clouds too large or too clustered for 16 levels of resolution to tell apart.
+#[cfg(not(feature = "wide-keys"))]
+pub type KeyType = u64;
+#[cfg(feature = "wide-keys")]
+pub type KeyType = u128;
+
+#[cfg(not(feature = "wide-keys"))]
 pub const DEEPEST_LEVEL: KeyType = 16;
+#[cfg(feature = "wide-keys")]
+pub const DEEPEST_LEVEL: KeyType = 40;
+
 pub const LEVEL_SIZE: KeyType = 1 << DEEPEST_LEVEL;
 pub const ROOT: MortonKey = MortonKey{anchor: [0, 0, 0], morton: 0};
 
@@ -234,7 +246,7 @@ impl MortonKey {
     pub fn box_coordinates(&self, domain: &Domain) -> Vec<f64> {
         let mut serialized = Vec::<f64>::with_capacity(24);
         let level = self.level();
-        let step = (1 << (DEEPEST_LEVEL - level)) as u64;
+        let step = (1 << (DEEPEST_LEVEL - level)) as KeyType;
 
         let anchors = [
             [self.anchor[0], self.anchor[1], self.anchor[2]],
@@ -364,7 +376,6 @@ fn find_level(morton: KeyType) -> KeyType {
 
 /// Helper function for decoding keys.
 fn decode_key_helper(key: KeyType, lookup_table: &[KeyType; 512]) -> KeyType {
-    const N_LOOPS: KeyType = 7; // 8 bytes in 64 bit key
     let mut coord: KeyType = 0;
 
     for index in 0..N_LOOPS {
@@ -374,10 +385,10 @@ fn decode_key_helper(key: KeyType, lookup_table: &[KeyType; 512]) -> KeyType {
     coord
 }
 
-/// Decode a given key.
+/// Decode a given key using the portable lookup-table path.
 ///
 /// Returns the anchor for the given Morton key
-fn decode_key(morton: KeyType) -> [KeyType; 3] {
+fn decode_key_tables(morton: KeyType) -> [KeyType; 3] {
     let key = morton >> LEVEL_DISPLACEMENT;
 
     let x = decode_key_helper(key, &X_LOOKUP_DECODE);
@@ -387,6 +398,21 @@ fn decode_key(morton: KeyType) -> [KeyType; 3] {
     [x, y, z]
 }
 
+/// Decode a given key.
+///
+/// Returns the anchor for the given Morton key. Dispatches to the BMI2 `pext` path on x86_64
+/// CPUs that support it, falling back to the portable lookup tables otherwise.
+fn decode_key(morton: KeyType) -> [KeyType; 3] {
+    #[cfg(all(target_arch = "x86_64", not(feature = "wide-keys")))]
+    {
+        if is_x86_feature_detected!("bmi2") {
+            return unsafe { decode_key_bmi2(morton) };
+        }
+    }
+
+    decode_key_tables(morton)
+}
+
 /// Map a point to the anchor of the enclosing box.
 ///
 /// Returns the 3 integeger coordinates of the enclosing box.
@@ -416,28 +442,104 @@ fn point_to_anchor(
     anchor
 }
 
-/// Encode an anchor.
+/// Encode an anchor using the portable lookup-table path.
 ///
 /// Returns the Morton key associated with the given anchor.
 ///
 /// # Arguments
 /// `anchor` - A vector with 4 elements defining the integer coordinates and level.
+fn encode_anchor_tables(anchor: &[KeyType; 3], level: KeyType) -> KeyType {
+    let mut key: KeyType = 0;
+
+    for byte_index in (0..N_BYTES).rev() {
+        let shift = byte_index as KeyType * BYTE_DISPLACEMENT;
+        let x_byte = ((anchor[0] >> shift) & BYTE_MASK) as usize;
+        let y_byte = ((anchor[1] >> shift) & BYTE_MASK) as usize;
+        let z_byte = ((anchor[2] >> shift) & BYTE_MASK) as usize;
+
+        key = (key << 24)
+            | Z_LOOKUP_ENCODE[z_byte]
+            | Y_LOOKUP_ENCODE[y_byte]
+            | X_LOOKUP_ENCODE[x_byte];
+    }
+
+    (key << LEVEL_DISPLACEMENT) | level
+}
+
+/// Encode an anchor.
+///
+/// Returns the Morton key associated with the given anchor. Dispatches to the BMI2 `pdep` path on
+/// x86_64 CPUs that support it, falling back to the portable lookup tables otherwise.
+///
+/// # Arguments
+/// `anchor` - The three integer coordinates.
+/// `level` - The level stored in the key's level bits.
 fn encode_anchor(anchor: &[KeyType; 3], level: KeyType) -> KeyType {
-    let x = anchor[0];
-    let y = anchor[1];
-    let z = anchor[2];
+    #[cfg(all(target_arch = "x86_64", not(feature = "wide-keys")))]
+    {
+        if is_x86_feature_detected!("bmi2") {
+            return unsafe { encode_anchor_bmi2(anchor, level) };
+        }
+    }
 
-    let key: KeyType = Z_LOOKUP_ENCODE[((z >> BYTE_DISPLACEMENT) & BYTE_MASK) as usize]
-        | Y_LOOKUP_ENCODE[((y >> BYTE_DISPLACEMENT) & BYTE_MASK) as usize]
-        | X_LOOKUP_ENCODE[((x >> BYTE_DISPLACEMENT) & BYTE_MASK) as usize];
+    encode_anchor_tables(anchor, level)
+}
+
+/// Encode a whole structure-of-arrays coordinate buffer in one pass.
+///
+/// Equivalent to calling [`encode_anchor`] on `[xs[i], ys[i], zs[i]]` for every `i`, but lets
+/// tree construction feed columnar point data straight from three separate slices instead of
+/// paying for an interleaved `[KeyType; 3]` per point first.
+///
+/// # Panics
+/// Panics if `xs`, `ys` and `zs` don't all have the same length.
+pub fn encode_anchors(xs: &[KeyType], ys: &[KeyType], zs: &[KeyType], level: KeyType) -> Vec<KeyType> {
+    assert_eq!(xs.len(), ys.len());
+    assert_eq!(xs.len(), zs.len());
+
+    izip!(xs, ys, zs)
+        .map(|(&x, &y, &z)| encode_anchor(&[x, y, z], level))
+        .collect()
+}
 
-    let key = (key << 24)
-        | Z_LOOKUP_ENCODE[(z & BYTE_MASK) as usize]
-        | Y_LOOKUP_ENCODE[(y & BYTE_MASK) as usize]
-        | X_LOOKUP_ENCODE[(x & BYTE_MASK) as usize];
+/// Bit `i*3` set for every `i`: the interleaved slot the x-coordinate's bits land in. `MASK_Y`/
+/// `MASK_Z` are the same pattern shifted up by one and two bits, for y and z.
+#[cfg(all(target_arch = "x86_64", not(feature = "wide-keys")))]
+const MASK_X: u64 = 0x1249_2492_4924_9249;
+#[cfg(all(target_arch = "x86_64", not(feature = "wide-keys")))]
+const MASK_Y: u64 = MASK_X << 1;
+#[cfg(all(target_arch = "x86_64", not(feature = "wide-keys")))]
+const MASK_Z: u64 = MASK_X << 2;
+
+/// BMI2 `pdep`-based encoder. Bit-identical to [`encode_anchor_tables`] for every anchor up to
+/// `DEEPEST_LEVEL`; only called once `is_x86_feature_detected!("bmi2")` has confirmed the CPU
+/// supports the instruction this compiles to.
+#[cfg(all(target_arch = "x86_64", not(feature = "wide-keys")))]
+#[target_feature(enable = "bmi2")]
+unsafe fn encode_anchor_bmi2(anchor: &[KeyType; 3], level: KeyType) -> KeyType {
+    use std::arch::x86_64::_pdep_u64;
+
+    let interleaved = _pdep_u64(anchor[0], MASK_X)
+        | _pdep_u64(anchor[1], MASK_Y)
+        | _pdep_u64(anchor[2], MASK_Z);
+
+    (interleaved << LEVEL_DISPLACEMENT) | level
+}
+
+/// BMI2 `pext`-based decoder, the inverse of [`encode_anchor_bmi2`]. Bit-identical to
+/// [`decode_key_tables`] for every key produced by either encoder.
+#[cfg(all(target_arch = "x86_64", not(feature = "wide-keys")))]
+#[target_feature(enable = "bmi2")]
+unsafe fn decode_key_bmi2(morton: KeyType) -> [KeyType; 3] {
+    use std::arch::x86_64::_pext_u64;
+
+    let key = morton >> LEVEL_DISPLACEMENT;
 
-    let key = key << LEVEL_DISPLACEMENT;
-    key | level
+    [
+        _pext_u64(key, MASK_X),
+        _pext_u64(key, MASK_Y),
+        _pext_u64(key, MASK_Z),
+    ]
 }
 
 const X_LOOKUP_ENCODE: [KeyType; 256] = [
@@ -603,10 +705,31 @@ const Z_LOOKUP_DECODE: [KeyType; 512] = [
 ];
 
 // Number of bits used for Level information.
+#[cfg(not(feature = "wide-keys"))]
 const LEVEL_DISPLACEMENT: usize = 15;
+#[cfg(feature = "wide-keys")]
+const LEVEL_DISPLACEMENT: usize = 8;
 
-// Mask for the last 15 bits.
+// Mask for the level bits.
+#[cfg(not(feature = "wide-keys"))]
 const LEVEL_MASK: KeyType = 0x7FFF;
+#[cfg(feature = "wide-keys")]
+const LEVEL_MASK: KeyType = 0xFF;
+
+// Number of bytes per coordinate that `encode_anchor`/`decode_key_helper` walk. Derived from
+// `DEEPEST_LEVEL`: `ceil(DEEPEST_LEVEL / 8)` bytes are needed to carry that many bits per axis.
+#[cfg(not(feature = "wide-keys"))]
+const N_BYTES: usize = 2;
+#[cfg(feature = "wide-keys")]
+const N_BYTES: usize = 5;
+
+// `decode_key_helper` consumes 9-bit chunks and contributes 3 decoded bits each, so it needs at
+// least `ceil(3 * N_BYTES * 8 / 9)` loop iterations to cover all `N_BYTES` encoded bytes per axis:
+// 6 for `u64` keys, which run 7 (the extra chunk holds only zero bits), and 14 for `wide-keys`.
+#[cfg(not(feature = "wide-keys"))]
+const N_LOOPS: KeyType = 7;
+#[cfg(feature = "wide-keys")]
+const N_LOOPS: KeyType = 14;
 
 // Mask for lowest order byte.
 const BYTE_MASK: KeyType = 0xFF;
@@ -709,4 +832,109 @@ mod tests {
 
         assert_eq!(anchor, actual);
     }
+
+    /// With the `wide-keys` feature off, `KeyType` is `u64` and `DEEPEST_LEVEL` is 16 — this test
+    /// (together with `test_wide_keys_width` below, run under `--features wide-keys`) is the
+    /// round-trip parameterized over both key widths.
+    #[test]
+    #[cfg(not(feature = "wide-keys"))]
+    fn test_default_key_width() {
+        assert_eq!(DEEPEST_LEVEL, 16);
+        let max_coord: KeyType = (1 << DEEPEST_LEVEL) - 1;
+        let anchor: [KeyType; 3] = [max_coord, max_coord, max_coord];
+        assert_eq!(decode_key(encode_anchor(&anchor, DEEPEST_LEVEL)), anchor);
+    }
+
+    /// The `wide-keys` counterpart to `test_default_key_width`: `KeyType` is `u128` and
+    /// `DEEPEST_LEVEL` is 40, so anchors well beyond the default 16-bit-per-axis cap still
+    /// round-trip.
+    #[test]
+    #[cfg(feature = "wide-keys")]
+    fn test_wide_keys_width() {
+        assert_eq!(DEEPEST_LEVEL, 40);
+        let max_coord: KeyType = (1 << DEEPEST_LEVEL) - 1;
+        let anchor: [KeyType; 3] = [max_coord, max_coord, max_coord];
+        assert_eq!(decode_key(encode_anchor(&anchor, DEEPEST_LEVEL)), anchor);
+    }
+
+    /// `encode_anchors`' batch output must match calling `encode_anchor` one anchor at a time.
+    #[test]
+    fn test_encode_anchors_matches_encode_anchor() {
+        let xs: [KeyType; 4] = [0, 1, 65535, 12345];
+        let ys: [KeyType; 4] = [0, 65535, 1, 6789];
+        let zs: [KeyType; 4] = [0, 65535, 65535, 54321];
+
+        let batch = encode_anchors(&xs, &ys, &zs, DEEPEST_LEVEL);
+
+        let expected: Vec<KeyType> = (0..xs.len())
+            .map(|i| encode_anchor(&[xs[i], ys[i], zs[i]], DEEPEST_LEVEL))
+            .collect();
+
+        assert_eq!(batch, expected);
+    }
+
+    /// Encoding/decoding must round-trip an anchor that fills every bit `DEEPEST_LEVEL` grants it,
+    /// not just the 16-bit-wide anchor `test_encoding_decoding` exercises above.
+    #[test]
+    fn test_encoding_decoding_at_deepest_level() {
+        let coord: KeyType = (1 << DEEPEST_LEVEL) - 1;
+        let anchor: [KeyType; 3] = [coord, coord, coord];
+
+        let actual = decode_key(encode_anchor(&anchor, DEEPEST_LEVEL));
+
+        assert_eq!(anchor, actual);
+    }
+
+    /// `parent`/`children`/`find_key_in_direction` must still hold at `DEEPEST_LEVEL` itself —
+    /// level 16 by default, well past it (40) under `wide-keys` — so the wider key type isn't
+    /// just round-tripping `encode_anchor`/`decode_key` in isolation.
+    #[test]
+    fn test_navigation_beyond_level_16() {
+        let level = DEEPEST_LEVEL;
+        let coord: KeyType = (1 << level) / 2;
+        let key = MortonKey::from_anchor(&[coord, coord, coord]);
+
+        assert_eq!(key.level(), level);
+
+        let parent = key.parent();
+        assert_eq!(parent.level(), level - 1);
+        assert!(parent.is_ancestor(&key));
+
+        let children = parent.children();
+        assert_eq!(children.len(), 8);
+        assert!(children.contains(&key));
+
+        let neighbour = key
+            .find_key_in_direction(&[1, 0, 0])
+            .expect("neighbour in bounds at the midpoint of the domain");
+        assert_eq!(neighbour.level(), level);
+        assert_ne!(neighbour, key);
+    }
+
+    /// The BMI2 `pdep`/`pext` path must agree bit-for-bit with the portable lookup tables,
+    /// wherever the CPU running the test actually supports it.
+    #[test]
+    #[cfg(all(target_arch = "x86_64", not(feature = "wide-keys")))]
+    fn test_bmi2_matches_tables() {
+        if !is_x86_feature_detected!("bmi2") {
+            return;
+        }
+
+        let anchors: [[KeyType; 3]; 3] = [
+            [0, 0, 0],
+            [65535, 65535, 65535],
+            [12345, 6789, 54321],
+        ];
+
+        for anchor in anchors {
+            let tables_morton = encode_anchor_tables(&anchor, DEEPEST_LEVEL);
+            let bmi2_morton = unsafe { encode_anchor_bmi2(&anchor, DEEPEST_LEVEL) };
+            assert_eq!(tables_morton, bmi2_morton);
+
+            let tables_anchor = decode_key_tables(tables_morton);
+            let bmi2_anchor = unsafe { decode_key_bmi2(bmi2_morton) };
+            assert_eq!(tables_anchor, bmi2_anchor);
+            assert_eq!(tables_anchor, anchor);
+        }
+    }
 }
@@ -0,0 +1,266 @@
+//! Compact, self-describing on-disk format for a linearized octree's sorted `Vec<MortonKey>`.
+//!
+//! Sibling to the `morton` module: this lets an already-linearized key set be checkpointed to
+//! disk and reloaded without recomputing it from points. A file is a small header (magic,
+//! version, the `Domain` that produced the keys, `DEEPEST_LEVEL`, and the key count) followed by
+//! LZ4-compressed blocks. Keys are assumed sorted (as a linearized tree's always are), so within
+//! a block only the first key's `morton()` is stored in full; every subsequent one is an LEB128
+//! varint delta, since sorted Morton ids sharing high bits compress and delta-encode well.
+//! `MortonKey::from_morton` recovers each key's anchor from its morton id alone on load, so
+//! anchors aren't stored at all.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::morton::{MortonKey, DEEPEST_LEVEL};
+use crate::types::{Domain, PointType};
+
+/// Magic bytes identifying a file written by `write_keys`.
+const MORTON_STREAM_MAGIC: [u8; 4] = *b"RTMK";
+
+/// Format version, bumped whenever the header or block layout changes incompatibly.
+const MORTON_STREAM_VERSION: u32 = 1;
+
+/// Number of keys grouped into a single LZ4-compressed, delta-varint-encoded block.
+const MORTON_STREAM_BLOCK_SIZE: usize = 1024;
+
+/// How hard `write_keys` tries to shrink each block.
+///
+/// `lz4_flex` doesn't expose a separate high-compression mode the way the reference `liblz4`'s
+/// `LZ4HC` does, so `High` currently compresses identically to `Fast`. Kept as a real, documented
+/// no-op rather than silently dropping the caller's choice, so wiring in an HC-capable backend
+/// later only touches `compress_block`, not every call site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionLevel {
+    Fast,
+    High,
+}
+
+fn compress_block(payload: &[u8], _level: CompressionLevel) -> Vec<u8> {
+    lz4_flex::compress(payload)
+}
+
+fn decompress_block(payload: &[u8], raw_len: usize) -> Vec<u8> {
+    lz4_flex::decompress(payload, raw_len)
+}
+
+/// Write `value` as an unsigned LEB128 varint.
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let mut byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value != 0 {
+            byte |= 0x80;
+        }
+        writer.write_all(&[byte])?;
+        if value == 0 {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Read an unsigned LEB128 varint.
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut value: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    Ok(value)
+}
+
+/// Serialize `keys` (need not already be sorted) and `domain` to `path`: a header recording the
+/// format version, `domain`'s `origin`/`diameter`, `DEEPEST_LEVEL`, and the total key count,
+/// followed by one LZ4-compressed, delta-varint-encoded block per `MORTON_STREAM_BLOCK_SIZE`
+/// keys.
+pub fn write_keys<P: AsRef<Path>>(
+    path: P,
+    domain: &Domain,
+    keys: &[MortonKey],
+    level: CompressionLevel,
+) -> io::Result<()> {
+    let mut sorted = keys.to_vec();
+    sorted.sort();
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+
+    writer.write_all(&MORTON_STREAM_MAGIC)?;
+    writer.write_all(&MORTON_STREAM_VERSION.to_le_bytes())?;
+    for coordinate in domain.origin {
+        writer.write_all(&coordinate.to_le_bytes())?;
+    }
+    for coordinate in domain.diameter {
+        writer.write_all(&coordinate.to_le_bytes())?;
+    }
+    writer.write_all(&(DEEPEST_LEVEL as u64).to_le_bytes())?;
+    writer.write_all(&(sorted.len() as u64).to_le_bytes())?;
+
+    for block in sorted.chunks(MORTON_STREAM_BLOCK_SIZE) {
+        let mut payload = Vec::new();
+        let mut previous = 0u64;
+        for key in block {
+            let morton = key.morton();
+            write_varint(&mut payload, morton.wrapping_sub(previous))?;
+            previous = morton;
+        }
+
+        let compressed = compress_block(&payload, level);
+
+        writer.write_all(&(block.len() as u64).to_le_bytes())?;
+        writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+        writer.write_all(&(compressed.len() as u64).to_le_bytes())?;
+        writer.write_all(&compressed)?;
+    }
+
+    Ok(())
+}
+
+/// Load a key stream previously written by `write_keys`.
+pub fn read_keys<P: AsRef<Path>>(path: P) -> io::Result<(Domain, Vec<MortonKey>)> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != MORTON_STREAM_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a morton key stream file",
+        ));
+    }
+
+    let mut version_buf = [0u8; 4];
+    reader.read_exact(&mut version_buf)?;
+    if u32::from_le_bytes(version_buf) != MORTON_STREAM_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unsupported morton key stream version",
+        ));
+    }
+
+    let mut origin = [0 as PointType; 3];
+    for coordinate in origin.iter_mut() {
+        let mut buf = [0u8; std::mem::size_of::<PointType>()];
+        reader.read_exact(&mut buf)?;
+        *coordinate = PointType::from_le_bytes(buf);
+    }
+    let mut diameter = [0 as PointType; 3];
+    for coordinate in diameter.iter_mut() {
+        let mut buf = [0u8; std::mem::size_of::<PointType>()];
+        reader.read_exact(&mut buf)?;
+        *coordinate = PointType::from_le_bytes(buf);
+    }
+    let domain = Domain { origin, diameter };
+
+    let mut deepest_level_buf = [0u8; 8];
+    reader.read_exact(&mut deepest_level_buf)?;
+    let deepest_level = u64::from_le_bytes(deepest_level_buf);
+    if deepest_level != DEEPEST_LEVEL as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "morton key stream was written with a different DEEPEST_LEVEL",
+        ));
+    }
+
+    let mut count_buf = [0u8; 8];
+    reader.read_exact(&mut count_buf)?;
+    let nkeys = u64::from_le_bytes(count_buf) as usize;
+
+    let mut keys = Vec::with_capacity(nkeys);
+
+    while keys.len() < nkeys {
+        let mut block_keys_buf = [0u8; 8];
+        reader.read_exact(&mut block_keys_buf)?;
+        let block_keys = u64::from_le_bytes(block_keys_buf) as usize;
+
+        let mut raw_len_buf = [0u8; 8];
+        reader.read_exact(&mut raw_len_buf)?;
+        let raw_len = u64::from_le_bytes(raw_len_buf) as usize;
+
+        let mut compressed_len_buf = [0u8; 8];
+        reader.read_exact(&mut compressed_len_buf)?;
+        let compressed_len = u64::from_le_bytes(compressed_len_buf) as usize;
+
+        let mut compressed = vec![0u8; compressed_len];
+        reader.read_exact(&mut compressed)?;
+        let payload = decompress_block(&compressed, raw_len);
+
+        let mut cursor = &payload[..];
+        let mut previous = 0u64;
+        for _ in 0..block_keys {
+            let delta = read_varint(&mut cursor)?;
+            let morton = previous.wrapping_add(delta);
+            keys.push(MortonKey::from_morton(morton));
+            previous = morton;
+        }
+    }
+
+    Ok((domain, keys))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+    use rand::SeedableRng;
+
+    use crate::octree::Tree;
+
+    fn refined_tree_fixture() -> (Domain, Vec<MortonKey>) {
+        let domain = Domain {
+            origin: [0., 0., 0.],
+            diameter: [1., 1., 1.],
+        };
+
+        let mut range = StdRng::seed_from_u64(0);
+        let between = rand::distributions::Uniform::from(0.0..1.0);
+
+        let keys: Vec<MortonKey> = (0..1000)
+            .map(|_| {
+                let point = [
+                    between.sample(&mut range),
+                    between.sample(&mut range),
+                    between.sample(&mut range),
+                ];
+                MortonKey::from_point(&point, &domain)
+            })
+            .collect();
+
+        let mut tree = Tree { keys };
+        tree.linearize();
+        tree.complete();
+
+        (domain, tree.keys)
+    }
+
+    #[test]
+    fn test_write_read_round_trip() {
+        let (domain, mut keys) = refined_tree_fixture();
+        keys.sort();
+
+        let mut path = std::env::temp_dir();
+        path.push("rusty_fast_solvers_morton_io_round_trip_test.bin");
+
+        write_keys(&path, &domain, &keys, CompressionLevel::High).unwrap();
+        let (read_domain, mut read_keys) = read_keys(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(read_domain.origin, domain.origin);
+        assert_eq!(read_domain.diameter, domain.diameter);
+
+        read_keys.sort();
+        assert_eq!(read_keys, keys);
+    }
+}
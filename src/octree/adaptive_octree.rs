@@ -1,5 +1,6 @@
 //! Data structures and functions for adaptive octrees.
 
+use super::morton::{MortonKey, RawMorton};
 use super::{Octree, OctreeType, Statistics};
 use ndarray::{Array1, ArrayView2, ArrayViewMut1, Axis};
 use rusty_kernel_tools::RealType;
@@ -13,25 +14,30 @@ pub enum BalanceMode {
     Balanced,
 }
 
-fn refine_tree<T: RealType>(
-    key: usize,
+/// Recursively subdivide `refine_indices` into `MAX_PARTICLES`-sized leaves under `key`.
+///
+/// Exposed at `pub(super)` visibility so [`super::incremental`] can re-run it on a single
+/// leaf's indices when a batched insert pushes that leaf over `max_particles`.
+pub(super) fn refine_tree<T: RealType>(
+    key: MortonKey,
     refine_indices: &HashSet<usize>,
-    mut particle_keys: ArrayViewMut1<usize>,
+    mut particle_keys: ArrayViewMut1<MortonKey>,
     particles: ArrayView2<T>,
     max_particles: usize,
+    max_level_cap: RawMorton,
     origin: &[f64; 3],
     diameter: &[f64; 3],
 ) {
-    use crate::morton::{encode_point, find_level};
+    use super::morton::{encode_point, find_level};
 
     let level = find_level(key);
 
-    if (level == 16) | (refine_indices.len() < max_particles) {
-        // Do not refine if we have reached level cap or
+    if (level == max_level_cap) | (refine_indices.len() < max_particles) {
+        // Do not refine if we have reached the level cap or
         // we are already below the particle limit.
         return;
     }
-    let mut new_keys = HashSet::<usize>::new();
+    let mut new_keys = HashSet::<MortonKey>::new();
 
     for &particle_index in refine_indices {
         let particle = [
@@ -57,6 +63,7 @@ fn refine_tree<T: RealType>(
             particle_keys.view_mut(),
             particles,
             max_particles,
+            max_level_cap,
             origin,
             diameter,
         );
@@ -71,10 +78,13 @@ fn refine_tree<T: RealType>(
 /// * `particles` - A (3, N) array of particles of type f32 or f64.
 /// * `max_particles` - The maximum number of particles in each leaf.
 /// * `balance_mode` - Use `Balanced` for a 2:1 balanced octree, `Unbalanced` otherwise.
+/// * `max_level_cap` - The deepest level refinement is allowed to reach; must not exceed
+///   `morton::MAX_LEVEL`.
 pub fn adaptive_octree<T: RealType>(
     particles: ArrayView2<T>,
     max_particles: usize,
     balance_mode: BalanceMode,
+    max_level_cap: RawMorton,
 ) -> Octree<'_, T> {
     use crate::helpers::compute_bounds;
 
@@ -93,7 +103,14 @@ pub fn adaptive_octree<T: RealType>(
         bounds[2][0].to_f64().unwrap(),
     ];
 
-    adaptive_octree_with_bounding_box(particles, max_particles, origin, diameter, balance_mode)
+    adaptive_octree_with_bounding_box(
+        particles,
+        max_particles,
+        origin,
+        diameter,
+        balance_mode,
+        max_level_cap,
+    )
 }
 
 /// Create an adaptive Octree with given bounding box.
@@ -106,33 +123,45 @@ pub fn adaptive_octree<T: RealType>(
 /// * `origin` - The origin of the bounding box.
 /// * `diameter` - The diameter of the bounding box in each dimension.
 /// * `balance_mode` - Use `Balanced` for a 2:1 balanced octree, `Unbalanced` otherwise.
+/// * `max_level_cap` - The deepest level refinement is allowed to reach; must not exceed
+///   `morton::MAX_LEVEL`.
 pub fn adaptive_octree_with_bounding_box<T: RealType>(
     particles: ArrayView2<T>,
     max_particles: usize,
     origin: [f64; 3],
     diameter: [f64; 3],
     balance_mode: BalanceMode,
+    max_level_cap: RawMorton,
 ) -> Octree<'_, T> {
+    use super::morton::MAX_LEVEL;
     use super::{
         compute_interaction_list_map, compute_leaf_map, compute_level_information,
         compute_near_field_map,
     };
 
+    assert!(
+        max_level_cap <= MAX_LEVEL,
+        "max_level_cap {} exceeds morton::MAX_LEVEL ({})",
+        max_level_cap,
+        MAX_LEVEL
+    );
+
     let number_of_particles = particles.len_of(Axis(1));
 
     let now = Instant::now();
 
     // First build up the non-adaptive tree by continuous refinement.
 
-    let mut particle_keys = Array1::<usize>::zeros(number_of_particles);
+    let mut particle_keys = Array1::from_elem(number_of_particles, MortonKey::root());
     let refine_indices: HashSet<usize> = (0..number_of_particles).collect();
 
     refine_tree(
-        0,
+        MortonKey::root(),
         &refine_indices,
         particle_keys.view_mut(),
         particles,
         max_particles,
+        max_level_cap,
         &origin,
         &diameter,
     );
@@ -182,9 +211,12 @@ pub fn adaptive_octree_with_bounding_box<T: RealType>(
     };
 
     Octree {
-        particles,
+        // Owned rather than borrowed so `Octree::append_particles` can grow it in place.
+        particles: particles.to_owned(),
         particle_keys,
         max_level,
+        max_particles,
+        max_level_cap,
         origin,
         diameter,
         leaf_key_to_particles,
@@ -200,20 +232,30 @@ pub fn adaptive_octree_with_bounding_box<T: RealType>(
     }
 }
 
-/// Take a key and add the key and all its ancestors to the tree
-fn find_completion(
-    mut key: usize,
-    level_keys: &mut HashMap<usize, HashSet<usize>>,
-    all_keys: &mut HashSet<usize>,
+/// Take a key and add the key and all its ancestors to the tree.
+///
+/// The walk stops as soon as it reaches a key already present in `all_keys`, or the
+/// level-0 root, whichever comes first — the root has no parent, so it is always the
+/// natural end of the line.
+///
+/// `pub(super)` so [`super::incremental`] can re-run it around a single split's near field
+/// instead of the whole tree.
+pub(super) fn find_completion(
+    mut key: MortonKey,
+    level_keys: &mut HashMap<RawMorton, HashSet<MortonKey>>,
+    all_keys: &mut HashSet<MortonKey>,
 ) {
-    use crate::morton::{find_level, find_parent};
+    use super::morton::{find_level, find_parent};
 
-    let mut intermediate_keys = HashSet::<usize>::new();
+    let mut intermediate_keys = HashSet::<MortonKey>::new();
     let mut level = find_level(key);
     while !all_keys.contains(&key) {
         intermediate_keys.insert(key);
         level_keys.get_mut(&level).unwrap().insert(key);
-        level = level - 1;
+        if level == 0 {
+            break;
+        }
+        level -= 1;
         key = find_parent(key);
     }
 
@@ -221,23 +263,23 @@ fn find_completion(
 }
 
 fn balance_tree<T: RealType>(
-    level_keys: &mut HashMap<usize, HashSet<usize>>,
-    mut particle_keys: ArrayViewMut1<usize>,
+    level_keys: &mut HashMap<RawMorton, HashSet<MortonKey>>,
+    mut particle_keys: ArrayViewMut1<MortonKey>,
     particles: ArrayView2<T>,
-    all_keys: &mut HashSet<usize>,
+    all_keys: &mut HashSet<MortonKey>,
     origin: &[f64; 3],
     diameter: &[f64; 3],
 ) {
     use super::compute_complete_regular_tree;
-    use crate::morton::{compute_near_field, encode_point, find_level, find_parent};
+    use super::morton::{compute_near_field, encode_point, find_level, find_parent};
 
-    let max_level = level_keys.keys().max().unwrap().clone();
+    let max_level = *level_keys.keys().max().unwrap();
     let nlevels = 1 + max_level;
 
     let regular_tree = compute_complete_regular_tree(particles, max_level, origin, diameter);
 
     for level in (1..nlevels).rev() {
-        let current_keys: HashSet<usize> =
+        let current_keys: HashSet<MortonKey> =
             level_keys.get(&level).unwrap().iter().copied().collect();
         for key in current_keys {
             let near_field = compute_near_field(key);
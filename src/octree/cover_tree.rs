@@ -0,0 +1,287 @@
+//! A cover tree spatial index for non-uniform point sets.
+//!
+//! The Morton octree subdivides space uniformly, which wastes depth on clustered data and
+//! degrades kNN pruning when points are far from evenly spread. `CoverTree` indexes the same
+//! `(3, N)` particle arrays by metric distance instead, keeping one node per point and
+//! maintaining, for every level `i` a node appears at:
+//!
+//! * **nesting** — a node present at level `i` is also present at level `i - 1`;
+//! * **covering** — every level-`i` child lies within `2^i` of its parent;
+//! * **separation** — distinct nodes at level `i` are more than `2^i` apart.
+//!
+//! Levels aren't stored explicitly on every node; instead a node implicitly reappears at each
+//! level finer than its insertion level until an actual child is attached, so `insert` only ever
+//! materializes one node per point.
+
+use ndarray::{ArrayView2, Axis};
+use rusty_kernel_tools::RealType;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// One indexed particle, plus whatever other particles were close enough to nest under it.
+struct Node {
+    point_index: usize,
+    children: Vec<Node>,
+}
+
+/// A cover tree over a `(3, N)` particle array, for metric nearest-neighbour search over
+/// non-uniformly distributed points.
+pub struct CoverTree<'a, T: RealType> {
+    particles: ArrayView2<'a, T>,
+    root: Option<Node>,
+    /// The level `i` such that every indexed point lies within `2^i` of the root's point.
+    top_level: i32,
+}
+
+fn distance<T: RealType>(particles: ArrayView2<T>, a: usize, b: usize) -> f64 {
+    (0..3)
+        .map(|dim| {
+            let diff = particles[[dim, a]].to_f64().unwrap() - particles[[dim, b]].to_f64().unwrap();
+            diff * diff
+        })
+        .sum::<f64>()
+        .sqrt()
+}
+
+fn distance_to_point<T: RealType>(particles: ArrayView2<T>, a: usize, point: [f64; 3]) -> f64 {
+    (0..3)
+        .map(|dim| {
+            let diff = particles[[dim, a]].to_f64().unwrap() - point[dim];
+            diff * diff
+        })
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Insert `point_index` under `node`, which is already known to cover it at `level` (i.e.
+/// `distance(node, point_index) <= 2^level`).
+///
+/// `node` is implicitly its own child one level finer (nesting), so it's always checked as a
+/// candidate alongside `node`'s actual children; whichever candidate is closest to `point_index`
+/// is recursed into at `level - 1`. The recursion bottoms out, and `point_index` is attached as a
+/// brand new child of `node`, as soon as neither `node` itself nor any existing child is within
+/// `2^(level - 1)` of it.
+fn insert_rec<T: RealType>(particles: ArrayView2<T>, node: &mut Node, point_index: usize, level: i32) {
+    let radius = 2f64.powi(level - 1);
+
+    let mut best: Option<usize> = None;
+    let mut best_dist = radius;
+    for (index, child) in node.children.iter().enumerate() {
+        let dist = distance(particles, child.point_index, point_index);
+        if dist <= best_dist {
+            best_dist = dist;
+            best = Some(index);
+        }
+    }
+
+    // Root growth (see `CoverTree::insert`) wraps the old root in a new node that shares its
+    // `point_index`, so that point is, by construction, exactly as close to `node` as it is to
+    // `best` whenever `best` is that wrapped child: a real tie, not a meaningful one. Breaking
+    // it towards `node` itself would recurse into `node` forever without ever stepping into the
+    // child, since `self_dist`/`best_dist` stay identical at every level — so ties against a
+    // same-point child must go to the child to make any progress into the real subtree.
+    let self_dist = distance(particles, node.point_index, point_index);
+    let tied_with_self_nested_child =
+        matches!(best, Some(index) if node.children[index].point_index == node.point_index);
+    if self_dist <= radius && self_dist <= best_dist && !tied_with_self_nested_child {
+        insert_rec(particles, node, point_index, level - 1);
+    } else {
+        match best {
+            Some(index) => insert_rec(particles, &mut node.children[index], point_index, level - 1),
+            None => node.children.push(Node {
+                point_index,
+                children: Vec::new(),
+            }),
+        }
+    }
+}
+
+/// Wraps a heap payload with an `f64` sort key, ordering purely on that key — the same pattern
+/// `query::ByDistance` uses, since `f64` isn't `Ord` but every distance fed in here is finite.
+struct ByDistance(f64, usize);
+
+impl PartialEq for ByDistance {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl Eq for ByDistance {}
+impl PartialOrd for ByDistance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.0.partial_cmp(&other.0)
+    }
+}
+impl Ord for ByDistance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap()
+    }
+}
+
+impl<'a, T: RealType> CoverTree<'a, T> {
+    /// Build a cover tree by inserting every particle in `particles` one at a time.
+    pub fn new(particles: ArrayView2<'a, T>) -> CoverTree<'a, T> {
+        let mut tree = CoverTree {
+            particles,
+            root: None,
+            top_level: 0,
+        };
+
+        for point_index in 0..particles.len_of(Axis(1)) {
+            tree.insert(point_index);
+        }
+
+        tree
+    }
+
+    /// Insert a single particle, growing the tree's `top_level` first if it lies farther from
+    /// the root than the root's current covering radius allows.
+    pub fn insert(&mut self, point_index: usize) {
+        let root = match &self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(Node {
+                    point_index,
+                    children: Vec::new(),
+                });
+                self.top_level = 0;
+                return;
+            }
+        };
+
+        while distance(self.particles, root.point_index, point_index) > 2f64.powi(self.top_level) {
+            self.top_level += 1;
+            let old_root = self.root.take().unwrap();
+            self.root = Some(Node {
+                point_index: old_root.point_index,
+                children: vec![old_root],
+            });
+        }
+
+        insert_rec(
+            self.particles,
+            self.root.as_mut().unwrap(),
+            point_index,
+            self.top_level,
+        );
+    }
+
+    /// The `k` particles closest to `query`, as `(particle_index, distance)` pairs sorted by
+    /// increasing distance.
+    ///
+    /// Descends the tree level by level, maintaining a bounded max-heap of the best `k`
+    /// candidates found so far. A subtree rooted at a node seen at `level` is only expanded if
+    /// its best possible distance to `query` — `distance(node, query) - 2^(level + 1)`, the
+    /// triangle-inequality bound given every descendant lies within the doubling sum of covering
+    /// radii `2^level + 2^(level - 1) + ... < 2^(level + 1)` of `node` — can still beat the
+    /// current k-th best.
+    pub fn knn(&self, query: [f64; 3], k: usize) -> Vec<(usize, f64)> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let root = match &self.root {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+
+        let mut best = BinaryHeap::<ByDistance>::new();
+        let mut frontier = vec![(root, self.top_level)];
+        // Root growth nests a node under a wrapper with the same `point_index`; count it once.
+        let mut seen = std::collections::HashSet::new();
+
+        while let Some((node, level)) = frontier.pop() {
+            let dist = distance_to_point(self.particles, node.point_index, query);
+
+            let first_visit = seen.insert(node.point_index);
+            if first_visit && best.len() < k {
+                best.push(ByDistance(dist, node.point_index));
+            } else if first_visit && dist < best.peek().unwrap().0 {
+                best.pop();
+                best.push(ByDistance(dist, node.point_index));
+            }
+
+            let subtree_bound = dist - 2f64.powi(level + 1);
+            if best.len() == k && subtree_bound > best.peek().unwrap().0 {
+                continue;
+            }
+
+            for child in &node.children {
+                frontier.push((child, level - 1));
+            }
+        }
+
+        let mut result: Vec<(usize, f64)> = best
+            .into_iter()
+            .map(|ByDistance(dist, point_index)| (point_index, dist))
+            .collect();
+        result.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ndarray::Array2;
+    use rand::prelude::*;
+    use rand::SeedableRng;
+
+    /// `knn` by brute-force linear scan, for checking `CoverTree::knn` against.
+    fn brute_force_knn(particles: ArrayView2<f64>, query: [f64; 3], k: usize) -> Vec<(usize, f64)> {
+        let mut all: Vec<(usize, f64)> = (0..particles.len_of(Axis(1)))
+            .map(|index| (index, distance_to_point(particles, index, query)))
+            .collect();
+        all.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        all.truncate(k);
+        all
+    }
+
+    #[test]
+    fn test_knn_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for trial in 0..30 {
+            let n = 50;
+            let mut flat = Vec::with_capacity(3 * n);
+            for _ in 0..n {
+                // A wide spread relative to the unit covering radius, so the tree grows its root
+                // (and hits the same-point wrapper chain) on essentially every trial.
+                flat.push(rng.gen_range(-100.0..100.0));
+                flat.push(rng.gen_range(-100.0..100.0));
+                flat.push(rng.gen_range(-100.0..100.0));
+            }
+            let particles = Array2::from_shape_vec((3, n), flat).unwrap();
+
+            let tree = CoverTree::new(particles.view());
+            let query = [
+                rng.gen_range(-100.0..100.0),
+                rng.gen_range(-100.0..100.0),
+                rng.gen_range(-100.0..100.0),
+            ];
+            let k = 4;
+
+            let mut got = tree.knn(query, k);
+            let mut expected = brute_force_knn(particles.view(), query, k);
+
+            assert_eq!(got.len(), expected.len(), "trial {trial}");
+
+            let got_indices: std::collections::HashSet<usize> =
+                got.iter().map(|&(index, _)| index).collect();
+            assert_eq!(
+                got_indices.len(),
+                got.len(),
+                "trial {trial}: knn returned duplicate point indices: {got:?}"
+            );
+
+            got.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            expected.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            for ((_, got_dist), (_, expected_dist)) in got.iter().zip(expected.iter()) {
+                assert!(
+                    (got_dist - expected_dist).abs() < 1e-9,
+                    "trial {trial}: got {got:?}, expected {expected:?}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Incremental, in-place updates to an assembled `Octree`.
+//!
+//! `adaptive_octree_with_bounding_box` builds a tree from scratch in a single pass. The
+//! methods here let a caller fold in a batch of new points, or drop a batch of existing ones,
+//! afterwards — touching only the leaves the batch actually affects instead of re-running the
+//! whole build.
+
+use super::adaptive_octree::refine_tree;
+use super::morton::{find_level, find_parent, MortonKey};
+use super::Octree;
+use ndarray::{concatenate, Array1, ArrayView2, Axis};
+use rusty_kernel_tools::RealType;
+use std::collections::HashSet;
+
+impl<'a, T: RealType> Octree<'a, T> {
+    /// Fold `new` particles into the tree, splitting any leaf that grows past
+    /// `self.max_particles`, then locally restoring the 2:1 balance invariant around every
+    /// leaf touched by a split.
+    pub fn append_particles(&mut self, new: ArrayView2<T>) {
+        let first_new_index = self.particles.len_of(Axis(1));
+        let number_of_new = new.len_of(Axis(1));
+        if number_of_new == 0 {
+            return;
+        }
+
+        self.particles = concatenate(Axis(1), &[self.particles.view(), new]).unwrap();
+        let placeholder_keys = Array1::from_elem(number_of_new, MortonKey::root());
+        self.particle_keys =
+            concatenate(Axis(0), &[self.particle_keys.view(), placeholder_keys.view()]).unwrap();
+
+        let mut touched_leaves = HashSet::<MortonKey>::new();
+
+        for local_index in 0..number_of_new {
+            let particle_index = first_new_index + local_index;
+            let point = [
+                new[[0, local_index]].to_f64().unwrap(),
+                new[[1, local_index]].to_f64().unwrap(),
+                new[[2, local_index]].to_f64().unwrap(),
+            ];
+
+            let leaf = self.find_current_leaf(point);
+            self.particle_keys[particle_index] = leaf;
+            self.leaf_key_to_particles
+                .entry(leaf)
+                .or_insert_with(HashSet::new)
+                .insert(particle_index);
+            touched_leaves.insert(leaf);
+        }
+
+        let mut affected = HashSet::<MortonKey>::new();
+        for leaf in touched_leaves {
+            affected.extend(self.split_leaf_if_oversized(leaf));
+        }
+
+        self.restore_local_balance(&affected);
+        self.recompute_statistics();
+    }
+
+    /// Remove `indices` from the tree, merging sibling leaves back into their parent once
+    /// their combined particle count drops below `self.max_particles`.
+    pub fn remove_particles(&mut self, indices: &[usize]) {
+        if indices.is_empty() {
+            return;
+        }
+
+        let mut touched_parents = HashSet::<MortonKey>::new();
+
+        for &particle_index in indices {
+            let leaf = self.particle_keys[particle_index];
+            if let Some(members) = self.leaf_key_to_particles.get_mut(&leaf) {
+                members.remove(&particle_index);
+                if members.is_empty() {
+                    self.leaf_key_to_particles.remove(&leaf);
+                }
+            }
+            if find_level(leaf) > 0 {
+                touched_parents.insert(find_parent(leaf));
+            }
+        }
+
+        // A merge can make a grandparent eligible too, so keep folding upward until a round
+        // does nothing.
+        while !touched_parents.is_empty() {
+            let mut next_round = HashSet::new();
+            for parent in touched_parents {
+                if let Some(grandparent) = self.merge_children_if_sparse(parent) {
+                    next_round.insert(grandparent);
+                }
+            }
+            touched_parents = next_round;
+        }
+
+        self.recompute_statistics();
+    }
+
+    /// Walk down from the root along the point's exact path, stopping at the first key that
+    /// the tree already records as a leaf.
+    fn find_current_leaf(&self, point: [f64; 3]) -> MortonKey {
+        use super::morton::encode_point;
+
+        let mut current = MortonKey::root();
+        let mut level = 0;
+        while !self.leaf_key_to_particles.contains_key(&current) {
+            level += 1;
+            let candidate = encode_point(&point, level, &self.origin, &self.diameter);
+            if !self.all_keys.contains(&candidate) {
+                break;
+            }
+            current = candidate;
+        }
+        current
+    }
+
+    /// If `leaf` now holds more than `self.max_particles` particles, re-run `refine_tree` on
+    /// just its index set and register every key that refinement produced. Returns the set of
+    /// new leaves, whose near field needs rebalancing.
+    fn split_leaf_if_oversized(&mut self, leaf: MortonKey) -> HashSet<MortonKey> {
+        let indices = match self.leaf_key_to_particles.get(&leaf) {
+            Some(members) if members.len() > self.max_particles => members.clone(),
+            _ => return HashSet::new(),
+        };
+
+        self.leaf_key_to_particles.remove(&leaf);
+
+        refine_tree(
+            leaf,
+            &indices,
+            self.particle_keys.view_mut(),
+            self.particles.view(),
+            self.max_particles,
+            self.max_level_cap,
+            &self.origin,
+            &self.diameter,
+        );
+
+        let mut new_leaves = HashSet::new();
+        for &particle_index in &indices {
+            let key = self.particle_keys[particle_index];
+            self.all_keys.insert(key);
+            self.level_keys
+                .entry(find_level(key))
+                .or_insert_with(HashSet::new)
+                .insert(key);
+            self.leaf_key_to_particles
+                .entry(key)
+                .or_insert_with(HashSet::new)
+                .insert(particle_index);
+            new_leaves.insert(key);
+        }
+        new_leaves
+    }
+
+    /// Re-run `find_completion` over the near field of every key in `leaves`, restoring the
+    /// 2:1 balance invariant in the neighborhood a split actually touched.
+    fn restore_local_balance(&mut self, leaves: &HashSet<MortonKey>) {
+        use super::adaptive_octree::find_completion;
+        use super::morton::compute_near_field;
+
+        for &leaf in leaves {
+            for near_field_key in compute_near_field(leaf) {
+                let parent = find_parent(near_field_key);
+                find_completion(parent, &mut self.level_keys, &mut self.all_keys);
+            }
+        }
+    }
+
+    /// If every child of `parent` is a leaf and their combined particle count is back under
+    /// `self.max_particles`, merge them into `parent`. Returns `parent`'s own parent when a
+    /// merge happened, since it may now be a further merge candidate.
+    fn merge_children_if_sparse(&mut self, parent: MortonKey) -> Option<MortonKey> {
+        let children = parent.children();
+
+        let mut combined = HashSet::new();
+        for child in &children {
+            if let Some(members) = self.leaf_key_to_particles.get(child) {
+                combined.extend(members.iter().copied());
+            } else if self.all_keys.contains(child)
+                && child.children().iter().any(|grandchild| self.all_keys.contains(grandchild))
+            {
+                // Still refined further down — can't collapse `parent` yet.
+                return None;
+            }
+            // Otherwise this octant holds no particles (never populated, or emptied by a removal).
+        }
+
+        if combined.len() >= self.max_particles {
+            return None;
+        }
+
+        for child in &children {
+            self.leaf_key_to_particles.remove(child);
+            self.all_keys.remove(child);
+            if let Some(level_set) = self.level_keys.get_mut(&find_level(*child)) {
+                level_set.remove(child);
+            }
+        }
+
+        for &particle_index in &combined {
+            self.particle_keys[particle_index] = parent;
+        }
+        self.leaf_key_to_particles.insert(parent, combined);
+
+        if find_level(parent) > 0 {
+            Some(find_parent(parent))
+        } else {
+            None
+        }
+    }
+
+    /// Recompute `self.statistics` from the current leaf map.
+    fn recompute_statistics(&mut self) {
+        self.statistics.number_of_particles = self.particles.len_of(Axis(1));
+        self.statistics.max_level = self
+            .level_keys
+            .keys()
+            .copied()
+            .max()
+            .unwrap_or(self.statistics.max_level);
+        self.statistics.number_of_leafs = self.leaf_key_to_particles.keys().len();
+        self.statistics.number_of_keys = self.all_keys.len();
+        self.statistics.minimum_number_of_particles_in_leaf = self
+            .leaf_key_to_particles
+            .values()
+            .map(|item| item.len())
+            .reduce(std::cmp::min)
+            .unwrap_or(0);
+        self.statistics.maximum_number_of_particles_in_leaf = self
+            .leaf_key_to_particles
+            .values()
+            .map(|item| item.len())
+            .reduce(std::cmp::max)
+            .unwrap_or(0);
+        self.statistics.average_number_of_particles_in_leaf = self
+            .leaf_key_to_particles
+            .values()
+            .map(|item| item.len())
+            .sum::<usize>() as f64
+            / (self.leaf_key_to_particles.keys().len() as f64);
+    }
+}
@@ -0,0 +1,153 @@
+//! Morton-key encoding for the adaptive octree builder in this module.
+//!
+//! A key pairs a Morton-interleaved octant path with its own explicit `level` field, rather
+//! than packing both into the low bits of a single `usize` the way the original `refine_tree`
+//! did. That packing was what forced the old hard-coded `level == 16` cap: on a 64-bit word,
+//! only so many bits could be spared for the level tag once the octant path claimed 3 bits per
+//! level. Splitting `level` out into its own field removes that coupling — `morton` only ever
+//! spends 3 bits per level of octant path, and `level` is free to grow independently, up to
+//! `MAX_LEVEL` (21 levels on a `u64`; 42 on a `u128` behind the `wide-keys` feature).
+
+use std::collections::HashSet;
+
+#[cfg(not(feature = "wide-keys"))]
+pub type RawMorton = u64;
+#[cfg(feature = "wide-keys")]
+pub type RawMorton = u128;
+
+/// The deepest level a `MortonKey` can represent without overflowing `RawMorton`'s 3-bit-per-
+/// level octant path.
+pub const MAX_LEVEL: RawMorton = (std::mem::size_of::<RawMorton>() as RawMorton * 8) / 3;
+
+/// A Morton-encoded octant path, paired with the level it was encoded at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MortonKey {
+    morton: RawMorton,
+    level: RawMorton,
+}
+
+impl MortonKey {
+    /// The root key, at level 0.
+    pub fn root() -> Self {
+        MortonKey { morton: 0, level: 0 }
+    }
+
+    /// The raw interleaved octant path, excluding the level.
+    pub fn morton(&self) -> RawMorton {
+        self.morton
+    }
+
+    /// The level this key was encoded at.
+    pub fn level(&self) -> RawMorton {
+        self.level
+    }
+
+    /// The integer octant coordinates this key's `morton` path decodes to, one component per
+    /// axis, each in `0..2^level`.
+    pub fn anchor(&self) -> [RawMorton; 3] {
+        let mut anchor = [0 as RawMorton; 3];
+        for bit in 0..self.level {
+            let octant = (self.morton >> (3 * (self.level - 1 - bit))) & 0b111;
+            anchor[0] |= (octant & 0b001) << bit;
+            anchor[1] |= ((octant & 0b010) >> 1) << bit;
+            anchor[2] |= ((octant & 0b100) >> 2) << bit;
+        }
+        anchor
+    }
+
+    /// The coarsest octant goes in the lowest 3 bits, as `children` and `find_parent` expect.
+    fn from_anchor(anchor: [RawMorton; 3], level: RawMorton) -> Self {
+        let mut morton: RawMorton = 0;
+        for bit in 0..level {
+            let x_bit = (anchor[0] >> bit) & 1;
+            let y_bit = (anchor[1] >> bit) & 1;
+            let z_bit = (anchor[2] >> bit) & 1;
+            morton |= (x_bit | (y_bit << 1) | (z_bit << 2)) << (3 * (level - 1 - bit));
+        }
+        MortonKey { morton, level }
+    }
+
+    /// The 8 keys one level below `self`, covering the octants of its box.
+    pub fn children(&self) -> Vec<MortonKey> {
+        (0..8 as RawMorton)
+            .map(|octant| MortonKey {
+                morton: self.morton | (octant << (3 * self.level)),
+                level: self.level + 1,
+            })
+            .collect()
+    }
+}
+
+/// Encode `point` as the `MortonKey` of the box enclosing it at `level`, within the bounding
+/// box described by `origin`/`diameter`.
+pub fn encode_point(
+    point: &[f64; 3],
+    level: RawMorton,
+    origin: &[f64; 3],
+    diameter: &[f64; 3],
+) -> MortonKey {
+    assert!(
+        level <= MAX_LEVEL,
+        "level {} exceeds MortonKey::MAX_LEVEL ({})",
+        level,
+        MAX_LEVEL
+    );
+
+    let side = (1u64 << level) as f64;
+    let mut anchor = [0 as RawMorton; 3];
+    for dim in 0..3 {
+        let normalized = ((point[dim] - origin[dim]) / diameter[dim]).clamp(0.0, 1.0 - f64::EPSILON);
+        anchor[dim] = (normalized * side) as RawMorton;
+    }
+
+    MortonKey::from_anchor(anchor, level)
+}
+
+/// The level `key` was encoded at. Equivalent to `key.level()`, kept as a free function so
+/// call sites written against the old `find_level(raw_key)` API only need their argument type
+/// updated, not their call shape.
+pub fn find_level(key: MortonKey) -> RawMorton {
+    key.level()
+}
+
+/// The parent of `key`, one level up.
+pub fn find_parent(key: MortonKey) -> MortonKey {
+    assert!(key.level() > 0, "the root key has no parent");
+    let shift = 3 * (key.level() - 1);
+    let mask = (1 << shift) - 1;
+    MortonKey {
+        morton: key.morton() & mask,
+        level: key.level() - 1,
+    }
+}
+
+/// The same-level keys that share a face, edge or corner with `key` (up to 26 boxes in 3D),
+/// clipped to the boxes that actually exist at `key`'s level.
+pub fn compute_near_field(key: MortonKey) -> HashSet<MortonKey> {
+    let level = key.level();
+    let anchor = key.anchor();
+    let side = 1i64 << level;
+
+    let mut near_field = HashSet::new();
+    for dx in -1..=1i64 {
+        for dy in -1..=1i64 {
+            for dz in -1..=1i64 {
+                if dx == 0 && dy == 0 && dz == 0 {
+                    continue;
+                }
+
+                let x = anchor[0] as i64 + dx;
+                let y = anchor[1] as i64 + dy;
+                let z = anchor[2] as i64 + dz;
+                if x < 0 || y < 0 || z < 0 || x >= side || y >= side || z >= side {
+                    continue;
+                }
+
+                let neighbor_anchor = [x as RawMorton, y as RawMorton, z as RawMorton];
+                near_field.insert(MortonKey::from_anchor(neighbor_anchor, level));
+            }
+        }
+    }
+
+    near_field
+}
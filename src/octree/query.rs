@@ -0,0 +1,332 @@
+//! Spatial range queries over an assembled `Octree`.
+
+use super::morton::MortonKey;
+use super::Octree;
+use rusty_kernel_tools::RealType;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+
+/// Wraps a heap payload with an `f64` sort key, ordering purely on that key. `f64` isn't `Ord`
+/// (NaN has no sensible position), so `partial_cmp().unwrap()` is used directly; distances fed
+/// in here are always finite.
+#[derive(Debug, Clone, Copy)]
+struct ByDistance<K>(f64, K);
+
+impl<K> PartialEq for ByDistance<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K> Eq for ByDistance<K> {}
+
+impl<K> PartialOrd for ByDistance<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+impl<K> Ord for ByDistance<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap()
+    }
+}
+
+/// The squared distance from `point` to the closest point of the axis-aligned box
+/// `[lower, upper]`, or `0.0` if `point` lies inside it.
+fn min_dist_squared(point: [f64; 3], lower: [f64; 3], upper: [f64; 3]) -> f64 {
+    (0..3)
+        .map(|dim| {
+            if point[dim] < lower[dim] {
+                (lower[dim] - point[dim]).powi(2)
+            } else if point[dim] > upper[dim] {
+                (point[dim] - upper[dim]).powi(2)
+            } else {
+                0.0
+            }
+        })
+        .sum()
+}
+
+impl<'a, T: RealType> Octree<'a, T> {
+    /// The `k` particles closest to `point`, as `(particle_index, distance)` pairs sorted by
+    /// increasing distance.
+    ///
+    /// Performs a best-first descent: nodes are explored in order of their box's minimum
+    /// possible distance to `point`, and the search stops as soon as that bound exceeds the
+    /// distance to the current k-th best candidate, since every unexplored node must then be
+    /// farther away than the full-precision set of `k` found so far.
+    pub fn k_nearest(&self, point: [f64; 3], k: usize) -> Vec<(usize, f64)> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut frontier = BinaryHeap::new();
+        frontier.push(Reverse(ByDistance(0.0, MortonKey::root())));
+
+        // Bounded max-heap: the top is always the worst of the `k` best candidates found so far.
+        let mut best = BinaryHeap::<ByDistance<usize>>::new();
+
+        while let Some(Reverse(ByDistance(node_dist_sq, key))) = frontier.pop() {
+            if best.len() == k && node_dist_sq > best.peek().unwrap().0 {
+                break;
+            }
+
+            if let Some(indices) = self.leaf_key_to_particles.get(&key) {
+                for &particle_index in indices {
+                    let particle = [
+                        self.particles[[0, particle_index]].to_f64().unwrap(),
+                        self.particles[[1, particle_index]].to_f64().unwrap(),
+                        self.particles[[2, particle_index]].to_f64().unwrap(),
+                    ];
+                    let dist_sq: f64 = (0..3).map(|dim| (particle[dim] - point[dim]).powi(2)).sum();
+
+                    if best.len() < k {
+                        best.push(ByDistance(dist_sq, particle_index));
+                    } else if dist_sq < best.peek().unwrap().0 {
+                        best.pop();
+                        best.push(ByDistance(dist_sq, particle_index));
+                    }
+                }
+                continue;
+            }
+
+            for child in key.children() {
+                if self.all_keys.contains(&child) {
+                    let (lower, upper) = self.key_extent(child);
+                    frontier.push(Reverse(ByDistance(min_dist_squared(point, lower, upper), child)));
+                }
+            }
+        }
+
+        let mut result: Vec<(usize, f64)> = best
+            .into_iter()
+            .map(|ByDistance(dist_sq, particle_index)| (particle_index, dist_sq.sqrt()))
+            .collect();
+        result.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        result
+    }
+
+    /// The `k` particles closest to `point`, found by walking outward from `point`'s containing
+    /// leaf through the precomputed `near_field` adjacency instead of descending from the root.
+    ///
+    /// The frontier starts at the leaf containing `point` and expands to each visited box's
+    /// `near_field` neighbours; a box is only expanded once the bound on its closest possible
+    /// distance to `point` still beats the current k-th best candidate, so the search stops
+    /// spreading once every remaining neighbour is provably farther away than what's already
+    /// been found. Unlike `k_nearest`, this never walks back up through ancestors, so it only
+    /// explores boxes reachable via near-field links from the starting leaf.
+    pub fn knn(&self, point: [f64; 3], k: usize) -> Vec<(usize, f64)> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let start = self.find_leaf(point);
+
+        let mut visited = HashSet::new();
+        visited.insert(start);
+        let mut frontier = VecDeque::new();
+        frontier.push_back(start);
+
+        let mut best = BinaryHeap::<ByDistance<usize>>::new();
+
+        while let Some(key) = frontier.pop_front() {
+            let (lower, upper) = self.key_extent(key);
+            let bound = min_dist_squared(point, lower, upper);
+            if best.len() == k && bound > best.peek().unwrap().0 {
+                continue;
+            }
+
+            if let Some(indices) = self.leaf_key_to_particles.get(&key) {
+                for &particle_index in indices {
+                    let particle = [
+                        self.particles[[0, particle_index]].to_f64().unwrap(),
+                        self.particles[[1, particle_index]].to_f64().unwrap(),
+                        self.particles[[2, particle_index]].to_f64().unwrap(),
+                    ];
+                    let dist_sq: f64 =
+                        (0..3).map(|dim| (particle[dim] - point[dim]).powi(2)).sum();
+
+                    if best.len() < k {
+                        best.push(ByDistance(dist_sq, particle_index));
+                    } else if dist_sq < best.peek().unwrap().0 {
+                        best.pop();
+                        best.push(ByDistance(dist_sq, particle_index));
+                    }
+                }
+            }
+
+            if let Some(neighbours) = self.near_field.get(&key) {
+                for &neighbour in neighbours {
+                    if visited.insert(neighbour) {
+                        frontier.push_back(neighbour);
+                    }
+                }
+            }
+        }
+
+        let mut result: Vec<(usize, f64)> = best
+            .into_iter()
+            .map(|ByDistance(dist_sq, particle_index)| (particle_index, dist_sq.sqrt()))
+            .collect();
+        result.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        result
+    }
+
+    /// Every particle within `radius` of `point`, found the same way `knn` walks outward
+    /// through `near_field` from `point`'s containing leaf, but pruning against a fixed radius
+    /// rather than a shrinking k-th-best bound.
+    pub fn within_radius(&self, point: [f64; 3], radius: f64) -> Vec<(usize, f64)> {
+        let radius_sq = radius * radius;
+
+        let start = self.find_leaf(point);
+
+        let mut visited = HashSet::new();
+        visited.insert(start);
+        let mut frontier = VecDeque::new();
+        frontier.push_back(start);
+
+        let mut result = Vec::new();
+
+        while let Some(key) = frontier.pop_front() {
+            let (lower, upper) = self.key_extent(key);
+            if min_dist_squared(point, lower, upper) > radius_sq {
+                continue;
+            }
+
+            if let Some(indices) = self.leaf_key_to_particles.get(&key) {
+                for &particle_index in indices {
+                    let particle = [
+                        self.particles[[0, particle_index]].to_f64().unwrap(),
+                        self.particles[[1, particle_index]].to_f64().unwrap(),
+                        self.particles[[2, particle_index]].to_f64().unwrap(),
+                    ];
+                    let dist_sq: f64 =
+                        (0..3).map(|dim| (particle[dim] - point[dim]).powi(2)).sum();
+                    if dist_sq <= radius_sq {
+                        result.push((particle_index, dist_sq.sqrt()));
+                    }
+                }
+            }
+
+            if let Some(neighbours) = self.near_field.get(&key) {
+                for &neighbour in neighbours {
+                    if visited.insert(neighbour) {
+                        frontier.push_back(neighbour);
+                    }
+                }
+            }
+        }
+
+        result.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        result
+    }
+
+    /// The leaf whose box contains `point`: descends from the root through whichever child's
+    /// extent contains `point`, the starting point for `knn`/`within_radius`'s near-field walk.
+    fn find_leaf(&self, point: [f64; 3]) -> MortonKey {
+        let mut current = MortonKey::root();
+
+        loop {
+            if self.leaf_key_to_particles.contains_key(&current) {
+                return current;
+            }
+
+            let mut descended = false;
+            for child in current.children() {
+                if self.all_keys.contains(&child) {
+                    let (lower, upper) = self.key_extent(child);
+                    if (0..3).all(|dim| point[dim] >= lower[dim] && point[dim] <= upper[dim]) {
+                        current = child;
+                        descended = true;
+                        break;
+                    }
+                }
+            }
+
+            if !descended {
+                return current;
+            }
+        }
+    }
+
+    /// The indices of every particle lying within the axis-aligned box `[lower, upper]`.
+    pub fn particles_in_box(&self, lower: [f64; 3], upper: [f64; 3]) -> Vec<usize> {
+        let mut result = Vec::new();
+        self.collect_in_box(MortonKey::root(), lower, upper, &mut result);
+        result
+    }
+
+    /// The spatial extent of `key`'s box, reconstructed from `origin`/`diameter` and the key's
+    /// level.
+    fn key_extent(&self, key: MortonKey) -> ([f64; 3], [f64; 3]) {
+        let anchor = key.anchor();
+        let side = (1u64 << key.level()) as f64;
+
+        let mut lower = [0.0; 3];
+        let mut upper = [0.0; 3];
+        for dim in 0..3 {
+            lower[dim] = self.origin[dim] + self.diameter[dim] * (anchor[dim] as f64) / side;
+            upper[dim] =
+                self.origin[dim] + self.diameter[dim] * (anchor[dim] as f64 + 1.0) / side;
+        }
+        (lower, upper)
+    }
+
+    /// Append every particle under `key` to `result`, without re-checking the query box —
+    /// used once a node has already been established to lie fully inside it.
+    fn collect_all(&self, key: MortonKey, result: &mut Vec<usize>) {
+        if let Some(indices) = self.leaf_key_to_particles.get(&key) {
+            result.extend(indices.iter().copied());
+            return;
+        }
+
+        for child in key.children() {
+            if self.all_keys.contains(&child) {
+                self.collect_all(child, result);
+            }
+        }
+    }
+
+    fn collect_in_box(
+        &self,
+        key: MortonKey,
+        lower: [f64; 3],
+        upper: [f64; 3],
+        result: &mut Vec<usize>,
+    ) {
+        let (box_lower, box_upper) = self.key_extent(key);
+
+        let disjoint = (0..3).any(|dim| box_upper[dim] < lower[dim] || box_lower[dim] > upper[dim]);
+        if disjoint {
+            return;
+        }
+
+        let fully_contained =
+            (0..3).all(|dim| box_lower[dim] >= lower[dim] && box_upper[dim] <= upper[dim]);
+        if fully_contained {
+            self.collect_all(key, result);
+            return;
+        }
+
+        if let Some(indices) = self.leaf_key_to_particles.get(&key) {
+            for &particle_index in indices {
+                let particle = [
+                    self.particles[[0, particle_index]].to_f64().unwrap(),
+                    self.particles[[1, particle_index]].to_f64().unwrap(),
+                    self.particles[[2, particle_index]].to_f64().unwrap(),
+                ];
+                if (0..3).all(|dim| particle[dim] >= lower[dim] && particle[dim] <= upper[dim]) {
+                    result.push(particle_index);
+                }
+            }
+            return;
+        }
+
+        for child in key.children() {
+            if self.all_keys.contains(&child) {
+                self.collect_in_box(child, lower, upper, result);
+            }
+        }
+    }
+}
@@ -96,8 +96,14 @@ pub fn regular_octree_with_bounding_box<T: RealType>(
     };
 
     Octree {
-        particles: particles,
+        // Owned rather than borrowed so `Octree::append_particles` can grow it in place.
+        particles: particles.to_owned(),
         max_level: max_level,
+        // A regular octree subdivides uniformly rather than by particle count, so there is no
+        // natural per-leaf cap; `usize::MAX` keeps `Octree::append_particles` from ever
+        // splitting a leaf it didn't build that way itself.
+        max_particles: usize::MAX,
+        max_level_cap: super::morton::MAX_LEVEL,
         origin: origin,
         diameter: diameter,
         level_keys: level_keys,
@@ -0,0 +1,81 @@
+//! Uniform subdomain-grid decomposition for embarrassingly-parallel interaction loops.
+//!
+//! `Octree::subdomain_grid` overlays a coarse uniform grid at a chosen level on top of the
+//! already-built adaptive tree, rather than walking the recursive structure directly. Each
+//! non-empty cell becomes an independent `Subdomain` carrying its own particles and the
+//! near-field "halo" keys its interaction list needs, so every unit can be processed without
+//! touching any other unit's share of the tree.
+
+use super::morton::{compute_near_field, MortonKey, RawMorton};
+use super::Octree;
+use rayon::prelude::*;
+use rusty_kernel_tools::RealType;
+
+/// One cell of a `subdomain_grid`: its key, the particles under it, and the near-field "halo"
+/// keys a caller needs present locally to build its interaction list.
+#[derive(Debug, Clone)]
+pub struct Subdomain {
+    pub key: MortonKey,
+    pub particles: Vec<usize>,
+    pub halo: Vec<MortonKey>,
+}
+
+impl<'a, T: RealType> Octree<'a, T> {
+    /// Partition the tree into independent work units on a coarse uniform grid at `level`.
+    ///
+    /// Every key present in the tree at `level` with at least one particle under it becomes one
+    /// `Subdomain` (keys with none are skipped), holding the indices of
+    /// every particle in its subtree and the near-field neighbours of `key` at `level` as its
+    /// halo — the ghost keys its interaction list needs but that belong to another unit. Units
+    /// are sorted by descending particle count, so driving them with `par_each_subdomain` hands
+    /// rayon's work-stealing scheduler the largest units first, the standard longest-processing-
+    /// time heuristic for balanced scheduling.
+    pub fn subdomain_grid(&self, level: RawMorton) -> Vec<Subdomain> {
+        let cells = match self.level_keys.get(&level) {
+            Some(cells) => cells,
+            None => return Vec::new(),
+        };
+
+        let mut subdomains: Vec<Subdomain> = cells
+            .iter()
+            .filter_map(|&key| {
+                let particles = self.particles_under(key);
+                if particles.is_empty() {
+                    return None;
+                }
+                let halo = compute_near_field(key).into_iter().collect();
+                Some(Subdomain { key, particles, halo })
+            })
+            .collect();
+
+        subdomains.sort_by(|a, b| b.particles.len().cmp(&a.particles.len()));
+        subdomains
+    }
+
+    /// Every particle index under `key`, gathered by descending into whichever of its
+    /// descendants are recorded as leaves.
+    fn particles_under(&self, key: MortonKey) -> Vec<usize> {
+        if let Some(indices) = self.leaf_key_to_particles.get(&key) {
+            return indices.iter().copied().collect();
+        }
+
+        let mut result = Vec::new();
+        for child in key.children() {
+            if self.all_keys.contains(&child) {
+                result.extend(self.particles_under(child));
+            }
+        }
+        result
+    }
+}
+
+/// Run `work` over every subdomain across rayon's thread pool.
+///
+/// Expects `subdomains` ordered largest-first, as returned by `Octree::subdomain_grid` — work-
+/// stealing then keeps every thread busy instead of leaving stragglers on the biggest cells.
+pub fn par_each_subdomain<F>(subdomains: &[Subdomain], work: F)
+where
+    F: Fn(&Subdomain) + Sync,
+{
+    subdomains.par_iter().for_each(work);
+}
@@ -6,15 +6,17 @@
 //! are actually being stored.
 
 use super::helpers::TreeStatistics;
-use ndarray::{Array1, ArrayView2, Axis};
+use ndarray::{concatenate, Array1, Array2, ArrayView2, Axis};
 use rayon::prelude::*;
 use rusty_kernel_tools::RealType;
 use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
-pub struct RegularOctree<'a, T: RealType> {
+pub struct RegularOctree<T: RealType> {
     /// A (3, N) array of N particles.
-    pub particles: ArrayView2<'a, T>,
+    ///
+    /// Owned rather than borrowed so `RegularOctree::insert_batch` can grow it in place.
+    pub particles: Array2<T>,
 
     /// The maximum level in the tree.
     pub max_level: usize,
@@ -57,7 +59,7 @@ pub struct RegularOctree<'a, T: RealType> {
 pub fn regular_octree<T: RealType>(
     particles: ArrayView2<T>,
     max_level: usize,
-) -> RegularOctree<'_, T> {
+) -> RegularOctree<T> {
     use super::helpers::compute_bounds;
 
     const TOL: f64 = 1E-5;
@@ -92,7 +94,7 @@ pub fn regular_octree_with_bounding_box<T: RealType>(
     max_level: usize,
     origin: [f64; 3],
     diameter: [f64; 3],
-) -> RegularOctree<'_, T> {
+) -> RegularOctree<T> {
     use super::morton::{compute_interaction_list, compute_near_field, encode_points, find_parent};
     use std::iter::FromIterator;
 
@@ -176,7 +178,7 @@ pub fn regular_octree_with_bounding_box<T: RealType>(
     };
 
     RegularOctree {
-        particles: particles,
+        particles: particles.to_owned(),
         max_level: max_level,
         origin: origin,
         diameter: diameter,
@@ -189,3 +191,97 @@ pub fn regular_octree_with_bounding_box<T: RealType>(
         statistics: statistics,
     }
 }
+
+impl<T: RealType> RegularOctree<T> {
+    /// Fold `new` particles into this tree as a second layer over the existing
+    /// `leaf_key_to_particles` map, instead of re-encoding every particle from scratch.
+    ///
+    /// A regular tree's depth never changes, so there's no splitting step like
+    /// `AdaptiveOctree::insert_batch`'s: each new particle is just re-encoded at the tree's
+    /// fixed `max_level` and attached to whichever key already owns that octant. Only the keys
+    /// that newly became non-empty (and their ancestors, walked up one level at a time) are
+    /// dirtied, and `near_field`/`interaction_list` are (re)computed for just that dirty set
+    /// rather than every key in the tree.
+    pub fn insert_batch(&mut self, new: ArrayView2<T>) {
+        use super::morton::{compute_interaction_list, compute_near_field, encode_points, find_parent};
+
+        let number_of_new = new.len_of(Axis(1));
+        if number_of_new == 0 {
+            return;
+        }
+
+        let first_new_index = self.particle_to_keys.len();
+        let new_keys = encode_points(new, self.max_level, &self.origin, &self.diameter);
+
+        self.particles = concatenate(Axis(1), &[self.particles.view(), new]).unwrap();
+        self.particle_to_keys =
+            concatenate(Axis(0), &[self.particle_to_keys.view(), new_keys.view()]).unwrap();
+
+        let mut dirty = HashSet::<usize>::new();
+
+        for (local_index, &key) in new_keys.iter().enumerate() {
+            let particle_index = first_new_index + local_index;
+
+            self.leaf_key_to_particles
+                .entry(key)
+                .or_insert_with(HashSet::new)
+                .insert(particle_index);
+
+            if self.all_keys.insert(key) {
+                dirty.insert(key);
+                self.level_keys
+                    .entry(self.max_level)
+                    .or_insert_with(HashSet::new)
+                    .insert(key);
+
+                let mut level = self.max_level;
+                let mut ancestor = key;
+                while level > 0 {
+                    ancestor = find_parent(ancestor);
+                    level -= 1;
+                    self.level_keys
+                        .entry(level)
+                        .or_insert_with(HashSet::new)
+                        .insert(ancestor);
+                    if !self.all_keys.insert(ancestor) {
+                        break;
+                    }
+                    dirty.insert(ancestor);
+                }
+            }
+        }
+
+        for &key in &dirty {
+            self.near_field
+                .entry(key)
+                .or_insert_with(HashSet::new)
+                .extend(&compute_near_field(key));
+            self.interaction_list
+                .entry(key)
+                .or_insert_with(HashSet::new)
+                .extend(&compute_interaction_list(key));
+        }
+
+        self.statistics.number_of_particles = self.particles.len_of(Axis(1));
+        self.statistics.number_of_leafs = self.leaf_key_to_particles.keys().len();
+        self.statistics.number_of_keys = self.all_keys.len();
+        self.statistics.minimum_number_of_particles_in_leaf = self
+            .leaf_key_to_particles
+            .values()
+            .map(|item| item.len())
+            .reduce(std::cmp::min)
+            .unwrap_or(0);
+        self.statistics.maximum_number_of_particles_in_leaf = self
+            .leaf_key_to_particles
+            .values()
+            .map(|item| item.len())
+            .reduce(std::cmp::max)
+            .unwrap_or(0);
+        self.statistics.average_number_of_particles_in_leaf = self
+            .leaf_key_to_particles
+            .values()
+            .map(|item| item.len())
+            .sum::<usize>() as f64
+            / (self.leaf_key_to_particles.keys().len() as f64);
+    }
+}
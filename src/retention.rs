@@ -0,0 +1,235 @@
+//! Retention-aware pruning and checkpoint/rollback for adaptively refined `MortonKey` sets.
+//!
+//! `octree::Tree` only knows how to linearize/complete a key set; it has no notion of which
+//! leaves an adaptive refinement loop can safely coarsen back away versus which ones must stick
+//! around (because they're interesting, or because they're part of a state the caller might want
+//! to return to). `RetentionTree` wraps a leaf set with exactly that bookkeeping: each key is
+//! tagged `Ephemeral`, `Marked`, or `Checkpoint`, `prune()` collapses any maximal subtree whose
+//! leaves are all `Ephemeral`, and `checkpoint`/`rewind_to` let a caller snapshot and restore a
+//! key set by id.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::morton::{MortonKey, ROOT};
+
+/// How protected a leaf is against `RetentionTree::prune`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Retention<CheckpointId> {
+    /// No protection; eligible for pruning once its whole sibling octet is also `Ephemeral`.
+    Ephemeral,
+    /// Part of the snapshot recorded by `checkpoint(id)`. `is_marked` remembers whether this key
+    /// was `Marked` at checkpoint time, so `mark`/protection checks don't need to special-case it.
+    Checkpoint { id: CheckpointId, is_marked: bool },
+    /// Permanently protected; never collapsed by `prune`, regardless of checkpoint history.
+    Marked,
+}
+
+/// A leaf set, coarsen/rollback-aware: every key currently present carries a [`Retention`], and
+/// past `checkpoint` calls are kept around so `rewind_to` can restore one exactly.
+#[derive(Debug)]
+pub struct RetentionTree<CheckpointId> {
+    pub keys: HashSet<MortonKey>,
+    retention: HashMap<MortonKey, Retention<CheckpointId>>,
+    checkpoints: HashMap<CheckpointId, Checkpoint<CheckpointId>>,
+}
+
+#[derive(Debug)]
+struct Checkpoint<CheckpointId> {
+    keys: HashSet<MortonKey>,
+    retention: HashMap<MortonKey, Retention<CheckpointId>>,
+}
+
+impl<CheckpointId: Clone + Eq + Hash> RetentionTree<CheckpointId> {
+    /// Wrap `keys`, initially all `Ephemeral`.
+    pub fn new(keys: impl IntoIterator<Item = MortonKey>) -> Self {
+        let keys: HashSet<MortonKey> = keys.into_iter().collect();
+        let retention = keys.iter().map(|&key| (key, Retention::Ephemeral)).collect();
+        RetentionTree {
+            keys,
+            retention,
+            checkpoints: HashMap::new(),
+        }
+    }
+
+    /// Add `key` as an `Ephemeral` leaf, if not already present.
+    pub fn insert(&mut self, key: MortonKey) {
+        self.keys.insert(key);
+        self.retention.entry(key).or_insert(Retention::Ephemeral);
+    }
+
+    /// Permanently protect `key` from `prune`.
+    pub fn mark(&mut self, key: MortonKey) {
+        match self.retention.entry(key) {
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                Retention::Checkpoint { is_marked, .. } => *is_marked = true,
+                retention => *retention = Retention::Marked,
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(Retention::Marked);
+            }
+        }
+    }
+
+    fn is_protected(&self, key: &MortonKey) -> bool {
+        matches!(
+            self.retention.get(key),
+            Some(Retention::Marked) | Some(Retention::Checkpoint { .. })
+        )
+    }
+
+    /// Collapse any maximal subtree whose leaves are all `Ephemeral` up to their common ancestor.
+    /// A sibling octet only collapses into its parent once every one of its 8 children is present
+    /// as a leaf and none is `Marked` or `Checkpoint`-protected; the parent itself then becomes a
+    /// new `Ephemeral` leaf, so a run of several all-`Ephemeral` levels collapses in one call.
+    /// `ROOT` has no parent, so it's never a candidate and is always retained.
+    pub fn prune(&mut self) {
+        loop {
+            let mut by_parent: HashMap<MortonKey, Vec<MortonKey>> = HashMap::new();
+            for &key in self.keys.iter() {
+                if key == ROOT {
+                    continue;
+                }
+                by_parent.entry(key.parent()).or_default().push(key);
+            }
+
+            let mut merges: Vec<(MortonKey, Vec<MortonKey>)> = Vec::new();
+            for (parent, mut children) in by_parent {
+                let mut expected = parent.children();
+                if children.len() != expected.len() {
+                    continue;
+                }
+                children.sort();
+                expected.sort();
+                if children != expected {
+                    continue;
+                }
+                if children.iter().any(|child| self.is_protected(child)) {
+                    continue;
+                }
+                merges.push((parent, children));
+            }
+
+            if merges.is_empty() {
+                break;
+            }
+
+            for (parent, children) in merges {
+                for child in &children {
+                    self.keys.remove(child);
+                    self.retention.remove(child);
+                }
+                self.keys.insert(parent);
+                self.retention.insert(parent, Retention::Ephemeral);
+            }
+        }
+    }
+
+    /// Record the current key set under `id`. Every currently-present key's retention becomes
+    /// `Checkpoint { id, is_marked }`, remembering whether it was `Marked` beforehand.
+    pub fn checkpoint(&mut self, id: CheckpointId) {
+        for &key in self.keys.iter() {
+            let is_marked = matches!(self.retention.get(&key), Some(Retention::Marked));
+            self.retention
+                .insert(key, Retention::Checkpoint { id: id.clone(), is_marked });
+        }
+
+        self.checkpoints.insert(
+            id,
+            Checkpoint {
+                keys: self.keys.clone(),
+                retention: self.retention.clone(),
+            },
+        );
+    }
+
+    /// Restore the key set (and every key's retention) to exactly what `checkpoint(id)` recorded,
+    /// discarding any keys inserted since.
+    pub fn rewind_to(&mut self, id: &CheckpointId) {
+        let snapshot = self
+            .checkpoints
+            .get(id)
+            .expect("no checkpoint recorded for this id");
+        self.keys = snapshot.keys.clone();
+        self.retention = snapshot.retention.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+    use rand::SeedableRng;
+
+    use crate::octree::Tree;
+    use crate::types::Domain;
+
+    fn complete_tree_fixture() -> Tree {
+        let domain = Domain {
+            origin: [0., 0., 0.],
+            diameter: [1., 1., 1.],
+        };
+
+        let mut range = StdRng::seed_from_u64(0);
+        let between = rand::distributions::Uniform::from(0.0..1.0);
+
+        let keys: Vec<MortonKey> = (0..1000)
+            .map(|_| {
+                let point = [
+                    between.sample(&mut range),
+                    between.sample(&mut range),
+                    between.sample(&mut range),
+                ];
+                MortonKey::from_point(&point, &domain)
+            })
+            .collect();
+
+        let mut tree = Tree { keys };
+        tree.linearize();
+        tree.complete();
+        tree
+    }
+
+    /// A complete tree's leaves exactly tile the root domain: each leaf covers `(1/8)^level` of
+    /// it, since every subdivision splits a box into 8 equal children, so the sum over leaves is
+    /// 1.0 and stays 1.0 under any collapse that merges whole sibling octets.
+    fn covered_volume(keys: &HashSet<MortonKey>) -> f64 {
+        keys.iter().map(|key| 8f64.powi(-(key.level() as i32))).sum()
+    }
+
+    #[test]
+    fn test_prune_preserves_coverage_and_marked_keys() {
+        let tree = complete_tree_fixture();
+        let mut retention = RetentionTree::<u64>::new(tree.keys.iter().cloned());
+
+        let before_volume = covered_volume(&retention.keys);
+
+        let marked = *tree.keys.first().unwrap();
+        retention.mark(marked);
+
+        retention.prune();
+
+        let after_volume = covered_volume(&retention.keys);
+        assert!((before_volume - after_volume).abs() < 1e-9);
+
+        assert!(retention.keys.contains(&marked));
+    }
+
+    #[test]
+    fn test_rewind_to_is_exact() {
+        let tree = complete_tree_fixture();
+        let mut retention = RetentionTree::<u64>::new(tree.keys.iter().cloned());
+
+        retention.checkpoint(0);
+        let snapshot = retention.keys.clone();
+
+        let refined = tree.keys.first().unwrap().children()[0];
+        retention.insert(refined);
+        assert_ne!(retention.keys, snapshot);
+
+        retention.rewind_to(&0);
+        assert_eq!(retention.keys, snapshot);
+    }
+}
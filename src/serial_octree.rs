@@ -1,7 +1,7 @@
 //! Algorithms for serial Octrees
 
-use crate::morton::MortonKey;
-// use crate::types::{Domain, KeyType, Point, Points};
+use crate::morton::{KeyType, MortonKey};
+// use crate::types::{Domain, Point, Points};
 use crate::DEEPEST_LEVEL;
 use itertools::Itertools;
 use std::collections::{HashMap, HashSet};
@@ -12,6 +12,107 @@ pub enum NodeType {
     LeafNode,
 }
 
+/// An ordering over octree boxes, used in place of `MortonKey`'s natural (Z-order) `Ord` when
+/// linearizing a tree. `Morton` is the default Z-order; `Hilbert` trades a slightly more
+/// expensive encoding for strictly better spatial locality across octant boundaries, which
+/// matters for partitioning but not for this serial crate's own ancestor-removal logic.
+pub trait SpaceFillingCurve {
+    /// Map a 3D integer anchor at `level` to its index along this curve.
+    fn encode(&self, anchor: &[KeyType; 3], level: KeyType) -> u64;
+
+    /// The sort key for `key`, used in place of `MortonKey`'s natural (Z-order) `Ord`.
+    fn sort_key(&self, key: &MortonKey) -> u64 {
+        self.encode(key.anchor(), key.level())
+    }
+}
+
+/// The default Z-order curve, i.e. `MortonKey`'s own bit-interleaved encoding.
+pub struct Morton;
+
+impl SpaceFillingCurve for Morton {
+    fn encode(&self, anchor: &[KeyType; 3], level: KeyType) -> u64 {
+        let mut code: u64 = 0;
+        for bit in (0..level).rev() {
+            for &component in anchor.iter() {
+                code = (code << 1) | (((component >> bit) & 1) as u64);
+            }
+        }
+        code
+    }
+
+    fn sort_key(&self, key: &MortonKey) -> u64 {
+        key.morton() as u64
+    }
+}
+
+/// A Hilbert-curve ordering, computed from a key's anchor and level.
+///
+/// Spatially adjacent boxes are much more likely to be adjacent in Hilbert order than in Morton
+/// order. Follows the standard "rotation" algorithm for converting an (x, y, z) index to a
+/// single Hilbert distance, applied one bit per level from coarsest to finest.
+pub struct Hilbert;
+
+impl SpaceFillingCurve for Hilbert {
+    fn encode(&self, anchor: &[KeyType; 3], level: KeyType) -> u64 {
+        let mut x = anchor[0];
+        let mut y = anchor[1];
+        let mut z = anchor[2];
+
+        let mut distance: u64 = 0;
+
+        let mut side = 1 << level;
+        while side > 1 {
+            side >>= 1;
+
+            let rx = if (x & side) > 0 { 1 } else { 0 };
+            let ry = if (y & side) > 0 { 1 } else { 0 };
+            let rz = if (z & side) > 0 { 1 } else { 0 };
+
+            let digit = (rx << 2) | (ry << 1) | rz;
+            distance = (distance << 3) | digit as u64;
+
+            // Rotate the remaining bits so the curve continues smoothly into the next octant.
+            if rz == 0 {
+                if ry == 1 {
+                    x = side - 1 - x;
+                    y = side - 1 - y;
+                } else {
+                    let tmp = x;
+                    x = y;
+                    y = tmp;
+                }
+
+                if rx == 1 {
+                    x = side - 1 - x;
+                    z = side - 1 - z;
+                }
+            }
+        }
+
+        distance
+    }
+}
+
+/// Remove ancestors from `keys` sorted by `MortonKey`'s natural (Z-order) `Ord`, keeping only the
+/// finest key along any ancestor/descendant chain. Z-order puts an ancestor directly before its
+/// first descendant, so checking adjacent pairs is enough; a list sorted by another curve does
+/// not have that property, so every `linearize*` variant calls this before re-sorting by curve.
+fn remove_ancestors(keys: Vec<MortonKey>) -> Vec<MortonKey> {
+    let nkeys = keys.len();
+    let mut new_keys = Vec::<MortonKey>::with_capacity(nkeys);
+
+    keys.into_iter().enumerate().tuple_windows::<((_, _), (_, _))>().for_each(|((_, a), (j, b))| {
+        if !a.is_ancestor(&b) {
+            new_keys.push(a.clone());
+        }
+        if j == (nkeys - 1) {
+            new_keys.push(b.clone());
+        }
+    });
+
+    new_keys
+}
+
 #[derive(Debug)]
 pub struct Tree {
     pub keys: HashSet<MortonKey>,
@@ -68,6 +169,18 @@ impl Tree {
 
         LinearTree { keys: Tree::linearize_keys(keys) }
     }
+
+    /// Like `linearize`, but returns the keys ordered by `curve` (e.g. `Hilbert`) instead of
+    /// `MortonKey`'s natural Z-order `Ord`. Ancestors are removed in Z-order first.
+    pub fn linearize_by_curve(&self, curve: &dyn SpaceFillingCurve) -> LinearTree {
+        let mut keys: Vec<MortonKey> = self.keys.iter().copied().collect::<Vec<MortonKey>>();
+        keys.sort();
+
+        let mut keys = remove_ancestors(keys);
+        keys.sort_by_key(|key| curve.sort_key(key));
+
+        LinearTree { keys }
+    }
 }
 
 impl LinearTree {
@@ -97,6 +210,18 @@ impl LinearTree {
         LinearTree { keys: new_keys }
     }
 
+    /// Like `linearize`, but returns the keys ordered by `curve` (e.g. `Hilbert`) instead of
+    /// `MortonKey`'s natural Z-order `Ord`. Ancestors are removed in Z-order first.
+    pub fn linearize_by_curve(&self, curve: &dyn SpaceFillingCurve) -> LinearTree {
+        let mut keys: Vec<MortonKey> = self.keys.iter().copied().collect::<Vec<MortonKey>>();
+        keys.sort();
+
+        let mut keys = remove_ancestors(keys);
+        keys.sort_by_key(|key| curve.sort_key(key));
+
+        LinearTree { keys }
+    }
+
     pub fn complete_region(a: &MortonKey, b: &MortonKey) -> Vec<MortonKey> {
         // let mut region = Vec::<MortonKey>::new();
         // let mut work_set = a.finest_ancestor(&b).children();
@@ -152,6 +277,59 @@ impl LinearTree {
     //     Tree::linearize_keys(region)
     }
 
+    /// Parallel, level-synchronous counterpart to `complete_region`, behind the `rayon`
+    /// feature: each round's `working_list` is tested and subdivided with `par_iter`/fold/
+    /// reduce instead of a serial loop, before moving on to the next round. Kept alongside
+    /// `complete_region` for correctness comparison.
+    #[cfg(feature = "rayon")]
+    pub fn complete_region_parallel(a: &MortonKey, b: &MortonKey) -> Vec<MortonKey> {
+        use rayon::prelude::*;
+
+        let a_ancestors: HashSet<MortonKey> = a.ancestors();
+        let b_ancestors: HashSet<MortonKey> = b.ancestors();
+
+        let mut working_list: HashSet<MortonKey> = a.finest_ancestor(&b).children().into_iter().collect();
+
+        let mut minimal_tree: Vec<MortonKey>;
+
+        loop {
+            let (aux_list, len): (HashSet<MortonKey>, usize) = working_list
+                .par_iter()
+                .fold(
+                    || (HashSet::new(), 0usize),
+                    |mut acc: (HashSet<MortonKey>, usize), w| {
+                        if ((a < w) & (w < b)) & !b_ancestors.contains(w) {
+                            acc.0.insert(*w);
+                            acc.1 += 1;
+                        } else if a_ancestors.contains(w) | b_ancestors.contains(w) {
+                            for child in w.children() {
+                                acc.0.insert(child);
+                            }
+                        }
+                        acc
+                    },
+                )
+                .reduce(
+                    || (HashSet::new(), 0usize),
+                    |mut a, b| {
+                        a.0.extend(b.0);
+                        a.1 += b.1;
+                        a
+                    },
+                );
+
+            if len == working_list.len() {
+                minimal_tree = aux_list.into_iter().collect();
+                break;
+            } else {
+                working_list = aux_list;
+            }
+        }
+
+        minimal_tree.sort();
+        minimal_tree
+    }
+
     pub fn complete(&self) -> CompleteLinearTree {
         let a = self.keys.iter().min().unwrap();
         let b = self.keys.iter().max().unwrap();
@@ -160,17 +338,207 @@ impl LinearTree {
         completion.push(b.clone());
         CompleteLinearTree{keys: completion}
     }
+
+    /// Insert `key` into this tree, keeping `keys` sorted and linear (ancestor-free): binary
+    /// search `key`'s sorted position, then drop any existing ancestor/descendant of `key`
+    /// within the local window around it (a node's descendants always occupy a contiguous run
+    /// in sorted order, so the conflicting window never extends past the first non-conflicting
+    /// neighbor on either side).
+    pub fn insert(&mut self, key: MortonKey) {
+        let idx = match self.keys.binary_search(&key) {
+            Ok(_) => return,
+            Err(idx) => idx,
+        };
+
+        let mut start = idx;
+        while start > 0
+            && (self.keys[start - 1].is_ancestor(&key) || key.is_ancestor(&self.keys[start - 1]))
+        {
+            start -= 1;
+        }
+
+        let mut end = idx;
+        while end < self.keys.len()
+            && (self.keys[end].is_ancestor(&key) || key.is_ancestor(&self.keys[end]))
+        {
+            end += 1;
+        }
+
+        self.keys.splice(start..end, [key]);
+    }
+
+    /// Remove `key` from this tree, if present, keeping `keys` sorted.
+    pub fn remove(&mut self, key: &MortonKey) {
+        if let Ok(idx) = self.keys.binary_search(key) {
+            self.keys.remove(idx);
+        }
+    }
+}
+
+/// A position in a `CompleteLinearTree`'s sorted leaves, supporting O(log n) seeks and
+/// contiguous range scans instead of a full linear scan. See `CompleteLinearTree::cursor`.
+pub struct Cursor<'a> {
+    keys: &'a [MortonKey],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(keys: &'a [MortonKey]) -> Self {
+        Cursor { keys, pos: 0 }
+    }
+
+    /// The key at the cursor's current position, or `None` if it has run off the end.
+    pub fn current(&self) -> Option<&MortonKey> {
+        self.keys.get(self.pos)
+    }
+
+    /// Move the cursor to the leaf containing `target`, the same binary search plus
+    /// ancestor-at-the-boundary check `CompleteLinearTree::find_leaf` uses, falling back to the
+    /// insertion point itself if `target` falls in a gap (which cannot happen for a genuinely
+    /// complete tree, but leaves the cursor in a sane spot rather than panicking).
+    pub fn seek(&mut self, target: &MortonKey) -> Option<&MortonKey> {
+        self.pos = match self.keys.binary_search(target) {
+            Ok(idx) => idx,
+            Err(idx) => {
+                if idx > 0 && self.keys[idx - 1].is_ancestor(target) {
+                    idx - 1
+                } else {
+                    idx
+                }
+            }
+        };
+
+        self.current()
+    }
+
+    /// Every leaf between `a` and `b` inclusive: seek to the leaf containing `a` and walk
+    /// forward in curve order until past the leaf containing `b`, the same min/max bracketing
+    /// `LinearTree::complete_region` uses for its endpoints.
+    pub fn leaves_between(&mut self, a: &MortonKey, b: &MortonKey) -> Vec<MortonKey> {
+        self.seek(a);
+
+        let mut leaves = Vec::new();
+        while let Some(&key) = self.current() {
+            if key > *b {
+                break;
+            }
+            leaves.push(key);
+            self.pos += 1;
+        }
+
+        leaves
+    }
 }
 
 impl CompleteLinearTree {
-    pub fn compute_interior_weights(
+    /// Locate the unique leaf containing `key`, in O(log n): binary search the sorted key
+    /// array, falling back to checking whether the key immediately before the insertion point
+    /// is an ancestor of `key` (the case where `key` is finer than any leaf, e.g. a point's
+    /// encoded key rather than a leaf itself).
+    pub fn find_leaf(&self, key: &MortonKey) -> Option<usize> {
+        match self.keys.binary_search(key) {
+            Ok(idx) => Some(idx),
+            Err(idx) => {
+                if idx > 0 && self.keys[idx - 1].is_ancestor(key) {
+                    Some(idx - 1)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// A seek cursor over this tree's sorted leaves, for locating and range-scanning leaves in
+    /// O(log n) / O(range size) instead of a full linear scan.
+    pub fn cursor(&self) -> Cursor {
+        Cursor::new(&self.keys)
+    }
+
+    /// Insert `key`, keeping `keys` sorted, linear (ancestor-free) and complete again: binary
+    /// search `key`'s sorted position, drop any existing ancestor/descendant of `key` within
+    /// the local window around it, then re-run `complete_region` between `key` and the
+    /// neighbor now bracketing it on each side to fill whatever gap the insertion opened up,
+    /// rather than re-running `complete` over the whole key set.
+    pub fn insert(&mut self, key: MortonKey) {
+        let idx = match self.keys.binary_search(&key) {
+            Ok(_) => return,
+            Err(idx) => idx,
+        };
+
+        let mut start = idx;
+        while start > 0
+            && (self.keys[start - 1].is_ancestor(&key) || key.is_ancestor(&self.keys[start - 1]))
+        {
+            start -= 1;
+        }
+
+        let mut end = idx;
+        while end < self.keys.len()
+            && (self.keys[end].is_ancestor(&key) || key.is_ancestor(&self.keys[end]))
+        {
+            end += 1;
+        }
+
+        let mut replacement = vec![key];
+
+        if start > 0 {
+            let left = self.keys[start - 1];
+            let mut gap = LinearTree::complete_region(&left, &key);
+            replacement.append(&mut gap);
+        }
+        if end < self.keys.len() {
+            let right = self.keys[end];
+            let mut gap = LinearTree::complete_region(&key, &right);
+            replacement.append(&mut gap);
+        }
+
+        replacement.sort();
+        self.keys.splice(start..end, replacement);
+    }
+
+    /// Remove `key` from this tree, if present, keeping `keys` sorted and complete again:
+    /// rather than re-running `complete` over the whole key set, only re-run `complete_region`
+    /// between the two neighbors the removal leaves bracketing the resulting gap.
+    pub fn remove(&mut self, key: &MortonKey) {
+        let idx = match self.keys.binary_search(key) {
+            Ok(idx) => idx,
+            Err(_) => return,
+        };
+
+        self.keys.remove(idx);
+
+        if idx > 0 && idx < self.keys.len() {
+            let left = self.keys[idx - 1];
+            let right = self.keys[idx];
+            let gap = LinearTree::complete_region(&left, &right);
+            self.keys.splice(idx..idx, gap);
+        }
+    }
+}
+
+/// A per-node aggregate folded bottom-up over a `CompleteLinearTree`, e.g. a particle count, a
+/// center of mass, or a bounding box. `identity`/`combine` must form a monoid: combining
+/// `identity()` with any summary must leave it unchanged, and `combine` must be associative.
+pub trait Summary: Clone {
+    /// The identity element: combining it with any summary leaves that summary unchanged.
+    fn identity() -> Self;
+
+    /// Combine this summary with another. Must be associative.
+    fn combine(&self, other: &Self) -> Self;
+}
+
+impl CompleteLinearTree {
+    /// Fold `leaf_values` (one summary per key, in the same order as `self.keys`) bottom up to
+    /// `root`, combining each node's summary into its parent's as we go and creating the
+    /// parent's entry the first time it's reached.
+    pub fn aggregate<S: Summary>(
         &self,
         root: &MortonKey,
-        weights: &Vec<f64>,
-    ) -> HashMap<MortonKey, f64> {
+        leaf_values: &[S],
+    ) -> HashMap<MortonKey, S> {
         assert!(
-            self.keys.len() == weights.len(),
-            "Keys and weights must have the same length."
+            self.keys.len() == leaf_values.len(),
+            "Keys and leaf values must have the same length."
         );
 
         assert!(
@@ -179,63 +547,132 @@ impl CompleteLinearTree {
             "`root` is not ancestor of the keys."
         );
 
-        let mut weights_map = HashMap::<MortonKey, f64>::new();
+        let mut summaries = HashMap::<MortonKey, S>::new();
+
+        // Traverse tree bottom up to compute all summaries
 
-        // Traverse tree bottom up to compute all weights
+        for (leaf, leaf_value) in self.keys.iter().copied().zip(leaf_values.iter().cloned()) {
+            summaries.insert(leaf, leaf_value.clone());
 
-        for (key, mut weight) in self.keys.iter().copied().zip(weights.iter().copied()) {
-            weights_map.insert(key, weight);
+            let mut key = leaf;
 
+            // Every ancestor up to `root` takes this leaf's own value once.
             while key != *root {
                 let parent = key.parent();
 
-                if let Some(parent_weight) = weights_map.get_mut(&parent) {
-                    *parent_weight += weight;
-                    weight = *parent_weight;
-                } else {
-                    weights_map.insert(parent, weight);
-                }
+                let summary = match summaries.get(&parent) {
+                    Some(parent_summary) => parent_summary.combine(&leaf_value),
+                    None => leaf_value.clone(),
+                };
+
+                summaries.insert(parent, summary);
+                key = parent;
             }
         }
 
-        weights_map
+        summaries
     }
 
-    pub fn coarsen_by_weights(
+    /// Parallel, level-synchronous counterpart to `aggregate`, behind the `rayon` feature:
+    /// keys are bucketed by `level()` and processed from `DEEPEST_LEVEL` upward with
+    /// `par_iter`, folding each level's per-key contributions into a per-parent `Vec<S>` via a
+    /// parallel fold/reduce before combining, so a level's work runs concurrently instead of
+    /// walking each leaf to `root` one at a time. Kept alongside `aggregate` for correctness
+    /// comparison.
+    #[cfg(feature = "rayon")]
+    pub fn aggregate_parallel<S: Summary + Send + Sync>(
         &self,
         root: &MortonKey,
-        weights: &Vec<f64>,
-        max_weight: f64,
+        leaf_values: &[S],
+    ) -> HashMap<MortonKey, S> {
+        use rayon::prelude::*;
+
+        assert!(
+            self.keys.len() == leaf_values.len(),
+            "Keys and leaf values must have the same length."
+        );
+
+        assert!(
+            root.is_ancestor(self.keys.first().unwrap())
+                && root.is_ancestor(self.keys.last().unwrap()),
+            "`root` is not ancestor of the keys."
+        );
+
+        let mut summaries: HashMap<MortonKey, S> = self
+            .keys
+            .iter()
+            .copied()
+            .zip(leaf_values.iter().cloned())
+            .collect();
+
+        for level in (1..=DEEPEST_LEVEL).rev() {
+            let level_keys: Vec<MortonKey> =
+                summaries.keys().filter(|key| key.level() == level).cloned().collect();
+
+            let by_parent: HashMap<MortonKey, Vec<S>> = level_keys
+                .par_iter()
+                .filter(|key| *key != root)
+                .filter_map(|key| summaries.get(key).map(|summary| (key.parent(), summary.clone())))
+                .fold(HashMap::new, |mut acc: HashMap<MortonKey, Vec<S>>, (parent, summary)| {
+                    acc.entry(parent).or_insert_with(Vec::new).push(summary);
+                    acc
+                })
+                .reduce(HashMap::new, |mut a, b| {
+                    for (key, mut values) in b {
+                        a.entry(key).or_insert_with(Vec::new).append(&mut values);
+                    }
+                    a
+                });
+
+            for (parent, children_summaries) in by_parent {
+                let combined = children_summaries
+                    .into_iter()
+                    .fold(S::identity(), |acc, summary| acc.combine(&summary));
+                summaries.insert(parent, combined);
+            }
+        }
+
+        summaries
+    }
+
+    /// Coarsen this tree bottom up: starting from `root`, refine into a node's children only
+    /// while `predicate` over its aggregated summary fails, keeping the node itself as soon as
+    /// `predicate` holds (or its children aren't present in the tree at all).
+    pub fn coarsen_by<S: Summary, P: Fn(&S) -> bool>(
+        &self,
+        root: &MortonKey,
+        leaf_values: &[S],
+        predicate: P,
     ) -> CompleteLinearTree {
-        fn coarsen_impl(
+        fn coarsen_impl<S: Summary>(
             key: &MortonKey,
-            weights: &HashMap<MortonKey, f64>,
+            summaries: &HashMap<MortonKey, S>,
             result_keys: &mut Vec<MortonKey>,
-            max_weight: f64,
+            predicate: &impl Fn(&S) -> bool,
         ) {
             if key.level() == DEEPEST_LEVEL {
                 // We are at deepest level. Have to add key.
                 result_keys.push(key.clone());
-            } else if *weights.get(key).unwrap() <= max_weight {
-                // Key is below threshold. Also add it.
+            } else if predicate(summaries.get(key).unwrap()) {
+                // Key satisfies the predicate. Also add it.
                 result_keys.push(key.clone());
             } else {
-                // Key is above threshold. Check if children are in tree.
-                if weights.contains_key(&key.first_child()) {
+                // Key fails the predicate. Check if children are in tree.
+                if summaries.contains_key(&key.first_child()) {
                     // Children are in tree. Therefore iterate through children.
                     for child in key.children() {
-                        coarsen_impl(&child, weights, result_keys, max_weight);
+                        coarsen_impl(&child, summaries, result_keys, predicate);
                     }
                 } else {
-                    // Children not in tree. Have to add key itself despite being too big.
+                    // Children not in tree. Have to add key itself despite failing the predicate.
                     result_keys.push(key.clone());
                 }
             }
         }
 
-        let weights_map = self.compute_interior_weights(&root, &weights);
+        let summaries = self.aggregate(root, leaf_values);
         let mut result_keys = Vec::<MortonKey>::with_capacity(self.keys.len());
-        coarsen_impl(root, &weights_map, &mut result_keys, max_weight);
+        coarsen_impl(root, &summaries, &mut result_keys, &predicate);
         result_keys.sort();
 
         CompleteLinearTree { keys: result_keys }
@@ -332,3 +769,131 @@ fn points_to_sorted_morton_keys(points: &Points, domain: &Domain) -> (Points, Ve
 
     (sorted_points, sorted_keys)
 } */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::prelude::*;
+    use rand::SeedableRng;
+
+    use crate::morton::ROOT;
+    use crate::types::Domain;
+
+    fn complete_tree_fixture(seed: u64, npoints: u64) -> CompleteLinearTree {
+        let domain = Domain { origin: [0., 0., 0.], diameter: [1., 1., 1.] };
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let between = rand::distributions::Uniform::from(0.0..1.0);
+
+        let keys: Vec<MortonKey> = (0..npoints)
+            .map(|_| {
+                let coordinate = [
+                    between.sample(&mut rng),
+                    between.sample(&mut rng),
+                    between.sample(&mut rng),
+                ];
+                MortonKey::from_point(&coordinate, &domain)
+            })
+            .collect();
+
+        Tree::from_iterable(keys.into_iter()).linearize().complete()
+    }
+
+    #[cfg(feature = "rayon")]
+    #[derive(Clone)]
+    struct CountSummary(usize);
+
+    #[cfg(feature = "rayon")]
+    impl Summary for CountSummary {
+        fn identity() -> Self {
+            CountSummary(0)
+        }
+
+        fn combine(&self, other: &Self) -> Self {
+            CountSummary(self.0 + other.0)
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_aggregate_parallel_matches_serial() {
+        let tree = complete_tree_fixture(0, 200);
+        let leaf_values: Vec<CountSummary> = tree.keys.iter().map(|_| CountSummary(1)).collect();
+
+        let serial = tree.aggregate(&ROOT, &leaf_values);
+        let parallel = tree.aggregate_parallel(&ROOT, &leaf_values);
+
+        assert_eq!(serial.len(), parallel.len());
+        for (key, summary) in serial.iter() {
+            assert_eq!(summary.0, parallel.get(key).unwrap().0);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_complete_region_parallel_matches_serial() {
+        let a: MortonKey = MortonKey { anchor: [0, 0, 0], morton: 0 };
+        let b: MortonKey = MortonKey {
+            anchor: [65535, 65535, 65535],
+            morton: 0b111111111111111111111111111111111111111111111111000000000010000,
+        };
+
+        let serial = LinearTree::complete_region(&a, &b);
+        let parallel = LinearTree::complete_region_parallel(&a, &b);
+
+        assert_eq!(serial, parallel);
+    }
+
+    #[test]
+    fn test_linear_tree_insert_then_remove_is_noop() {
+        let tree = complete_tree_fixture(0, 200);
+        let mut original = tree.keys.clone();
+        // Take a leaf out first, so inserting it back displaces no ancestor or descendant.
+        let new_key = original.remove(10);
+        let mut keys = LinearTree { keys: original.clone() };
+
+        keys.insert(new_key);
+        assert!(keys.keys.contains(&new_key));
+        keys.remove(&new_key);
+
+        assert_eq!(keys.keys, original);
+    }
+
+    #[test]
+    fn test_linear_tree_insert_displaces_ancestor() {
+        let mut keys = LinearTree {
+            keys: vec![ROOT.children()[0]],
+        };
+        let child = ROOT.children()[0].children()[0];
+
+        keys.insert(child);
+
+        assert_eq!(keys.keys, vec![child]);
+    }
+
+    #[test]
+    fn test_complete_linear_tree_insert_then_remove_is_noop() {
+        let tree = complete_tree_fixture(1, 200);
+        let mut tree = CompleteLinearTree { keys: tree.keys };
+        let original = tree.keys.clone();
+
+        let new_key = original[10].children()[0];
+        tree.insert(new_key);
+        assert!(tree.find_leaf(&new_key).is_some());
+        tree.remove(&new_key);
+
+        assert_eq!(tree.keys, original);
+    }
+
+    #[test]
+    fn test_complete_linear_tree_insert_stays_complete() {
+        let tree = complete_tree_fixture(2, 200);
+        let mut tree = CompleteLinearTree { keys: tree.keys };
+
+        let new_key = tree.keys[20].children()[0];
+        tree.insert(new_key);
+
+        assert_eq!(tree.keys, LinearTree { keys: tree.keys.clone() }.complete().keys);
+    }
+}